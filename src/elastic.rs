@@ -0,0 +1,513 @@
+// [[file:../lattice.note::8a5e1c37][8a5e1c37]]
+use crate::strain::{Strain, StrainMeasure};
+use crate::{BravaisLattice, Lattice};
+
+use gchemol_gut::prelude::*;
+use vecfx::nalgebra::{DMatrix, DVector, Matrix6};
+// 8a5e1c37 ends here
+
+// [[file:../lattice.note::d4b0e7f2][d4b0e7f2]]
+/// Crystal systems for the symmetry of elastic tensors. The conventional
+/// settings are assumed: the unique axis along z for tetragonal, trigonal
+/// and hexagonal systems, and along y for the monoclinic system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrystalSystem {
+    /// 21 independent elastic constants.
+    Triclinic,
+    /// 13 independent elastic constants, with the 2-fold axis along y.
+    Monoclinic,
+    /// 9 independent elastic constants, with the 2-fold axes along x, y
+    /// and z.
+    Orthorhombic,
+    /// 7 independent elastic constants (Laue class 4/m), with the 4-fold
+    /// axis along z.
+    Tetragonal,
+    /// 7 independent elastic constants (Laue class -3), with the 3-fold
+    /// axis along z as in the hexagonal setting with c along z. The
+    /// orientation is not checked by [`Lattice::elastic_deformations`] or
+    /// [`ElasticTensor::fit`].
+    Trigonal,
+    /// 5 independent elastic constants, with the 6-fold axis along z as in
+    /// the conventional cell with c along z (see
+    /// [`Orientation::CParallelZ`]). The orientation is not checked by
+    /// [`Lattice::elastic_deformations`] or [`ElasticTensor::fit`].
+    ///
+    /// [`Orientation::CParallelZ`]: crate::Orientation::CParallelZ
+    Hexagonal,
+    /// 3 independent elastic constants, with the 4-fold axes along x, y
+    /// and z.
+    Cubic,
+}
+
+impl From<BravaisLattice> for CrystalSystem {
+    fn from(bravais: BravaisLattice) -> Self {
+        use BravaisLattice::*;
+
+        match bravais {
+            Cub | Fcc | Bcc => Self::Cubic,
+            Tet | Bct1 | Bct2 => Self::Tetragonal,
+            Orc | Orcf1 | Orcf2 | Orcf3 | Orci | Orcc => Self::Orthorhombic,
+            Hex => Self::Hexagonal,
+            Rhl1 | Rhl2 => Self::Trigonal,
+            Mcl | Mclc1 | Mclc2 | Mclc3 | Mclc4 | Mclc5 => Self::Monoclinic,
+            Tri1a | Tri1b | Tri2a => Self::Triclinic,
+        }
+    }
+}
+
+impl CrystalSystem {
+    /// Strain patterns in Voigt notation required to determine all
+    /// independent elastic constants from stresses, following Le Page and
+    /// Saxe.
+    ///
+    /// Reference
+    /// ---------
+    /// - Le Page, Y.; Saxe, P. Phys. Rev. B 2002, 65, 104104.
+    pub fn strain_patterns(&self) -> Vec<[f64; 6]> {
+        match self {
+            Self::Cubic => vec![[1.0, 0.0, 0.0, 1.0, 0.0, 0.0]],
+            Self::Hexagonal | Self::Trigonal => vec![
+                [0.0, 0.0, 1.0, 1.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            ],
+            Self::Tetragonal => vec![
+                [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            ],
+            Self::Orthorhombic => vec![
+                [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            ],
+            Self::Monoclinic => vec![
+                [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0, 0.0, 1.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+                [0.0, 1.0, 0.0, 0.0, 0.0, 0.0],
+            ],
+            Self::Triclinic => (0..6)
+                .map(|i| {
+                    let mut p = [0.0; 6];
+                    p[i] = 1.0;
+                    p
+                })
+                .collect(),
+        }
+    }
+
+    // The independent elastic constants of the lowest Laue class in this
+    // system, each given by its contributions to Cij as (i, j, coefficient)
+    // with 1-based Voigt indices and i <= j.
+    fn elastic_constants(&self) -> Vec<Vec<(usize, usize, f64)>> {
+        let single =
+            |ij: &[(usize, usize)]| ij.iter().map(|&(i, j)| vec![(i, j, 1.0)]).collect_vec();
+        match self {
+            Self::Cubic => vec![
+                vec![(1, 1, 1.0), (2, 2, 1.0), (3, 3, 1.0)],
+                vec![(1, 2, 1.0), (1, 3, 1.0), (2, 3, 1.0)],
+                vec![(4, 4, 1.0), (5, 5, 1.0), (6, 6, 1.0)],
+            ],
+            // C66 = (C11 - C12)/2
+            Self::Hexagonal => vec![
+                vec![(1, 1, 1.0), (2, 2, 1.0), (6, 6, 0.5)],
+                vec![(1, 2, 1.0), (6, 6, -0.5)],
+                vec![(1, 3, 1.0), (2, 3, 1.0)],
+                vec![(3, 3, 1.0)],
+                vec![(4, 4, 1.0), (5, 5, 1.0)],
+            ],
+            // Laue class -3
+            Self::Trigonal => vec![
+                vec![(1, 1, 1.0), (2, 2, 1.0), (6, 6, 0.5)],
+                vec![(1, 2, 1.0), (6, 6, -0.5)],
+                vec![(1, 3, 1.0), (2, 3, 1.0)],
+                vec![(1, 4, 1.0), (2, 4, -1.0), (5, 6, 1.0)],
+                vec![(1, 5, 1.0), (2, 5, -1.0), (4, 6, -1.0)],
+                vec![(3, 3, 1.0)],
+                vec![(4, 4, 1.0), (5, 5, 1.0)],
+            ],
+            // Laue class 4/m
+            Self::Tetragonal => vec![
+                vec![(1, 1, 1.0), (2, 2, 1.0)],
+                vec![(1, 2, 1.0)],
+                vec![(1, 3, 1.0), (2, 3, 1.0)],
+                vec![(1, 6, 1.0), (2, 6, -1.0)],
+                vec![(3, 3, 1.0)],
+                vec![(4, 4, 1.0), (5, 5, 1.0)],
+                vec![(6, 6, 1.0)],
+            ],
+            Self::Orthorhombic => single(&[
+                (1, 1),
+                (2, 2),
+                (3, 3),
+                (1, 2),
+                (1, 3),
+                (2, 3),
+                (4, 4),
+                (5, 5),
+                (6, 6),
+            ]),
+            Self::Monoclinic => single(&[
+                (1, 1),
+                (2, 2),
+                (3, 3),
+                (1, 2),
+                (1, 3),
+                (2, 3),
+                (4, 4),
+                (5, 5),
+                (6, 6),
+                (1, 5),
+                (2, 5),
+                (3, 5),
+                (4, 6),
+            ]),
+            Self::Triclinic => single(
+                &(1..=6)
+                    .flat_map(|i| (i..=6).map(move |j| (i, j)))
+                    .collect_vec(),
+            ),
+        }
+    }
+}
+
+impl Lattice {
+    /// Return the strained lattices for fitting elastic constants of
+    /// crystal `system`, applying each strain pattern with each of the
+    /// strain `magnitudes` (e.g. ±0.5% and ±1%) as small strain.
+    pub fn elastic_deformations(
+        &self,
+        system: CrystalSystem,
+        magnitudes: &[f64],
+    ) -> Vec<(Strain, Lattice)> {
+        system
+            .strain_patterns()
+            .into_iter()
+            .flat_map(|p| magnitudes.iter().map(move |&x| p.map(|e| e * x)))
+            .map(|voigt| {
+                let strain = Strain::from_voigt(voigt);
+                let mut lat = *self;
+                lat.apply_strain(strain, StrainMeasure::Small, false);
+                (strain, lat)
+            })
+            .collect()
+    }
+}
+
+/// The elastic stiffness tensor in Voigt notation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ElasticTensor {
+    matrix: [[f64; 6]; 6],
+}
+
+impl ElasticTensor {
+    /// Construct from symmetric 6x6 stiffness matrix Cij.
+    pub fn new(matrix: [[f64; 6]; 6]) -> Self {
+        let symmetric = (0..6)
+            .flat_map(|i| (0..i).map(move |j| (i, j)))
+            .all(|(i, j)| {
+                let (x, y) = (matrix[i][j], matrix[j][i]);
+                (x - y).abs() <= 1e-8 * x.abs().max(y.abs())
+            });
+        assert!(symmetric, "asymmetric elastic tensor");
+        Self { matrix }
+    }
+
+    /// Fit the independent elastic constants of crystal `system` to
+    /// `stresses` under `strains` by least squares, using the linear
+    /// relation σ = C ε. Stresses are in Voigt order (xx, yy, zz, yz, xz,
+    /// xy), positive in tension, and determine the unit of the result.
+    pub fn fit(system: CrystalSystem, strains: &[Strain], stresses: &[[f64; 6]]) -> Result<Self> {
+        ensure!(
+            strains.len() == stresses.len(),
+            "strains and stresses mismatch"
+        );
+        let constants = system.elastic_constants();
+        let basis = constants.iter().map(|c| voigt_matrix(c)).collect_vec();
+
+        // σ_i = Σ_k c_k (B_k ε)_i
+        let n = strains.len();
+        let mut a = DMatrix::zeros(6 * n, constants.len());
+        let mut b = DVector::zeros(6 * n);
+        for (s, (strain, stress)) in strains.iter().zip(stresses).enumerate() {
+            let e = strain.to_voigt();
+            for i in 0..6 {
+                for (k, bk) in basis.iter().enumerate() {
+                    a[(6 * s + i, k)] = (0..6).map(|j| bk[i][j] * e[j]).sum();
+                }
+                b[6 * s + i] = stress[i];
+            }
+        }
+        let svd = a.svd(true, true);
+        let smax = svd.singular_values.max();
+        ensure!(
+            svd.singular_values.min() > 1e-8 * smax,
+            "strains are insufficient to determine all elastic constants"
+        );
+        let c = svd.solve(&b, 0.0).map_err(|e| format_err!("{e}"))?;
+        Ok(Self {
+            matrix: voigt_matrix_sum(&basis, c.as_slice()),
+        })
+    }
+
+    /// The stiffness matrix Cij.
+    pub fn matrix(&self) -> [[f64; 6]; 6] {
+        self.matrix
+    }
+
+    fn to_matrix6(self) -> Matrix6<f64> {
+        Matrix6::from_fn(|i, j| self.matrix[i][j])
+    }
+
+    /// The compliance matrix Sij, i.e. the inverse of Cij.
+    pub fn compliance(&self) -> Option<[[f64; 6]; 6]> {
+        let s = self.to_matrix6().try_inverse()?;
+        Some(std::array::from_fn(|i| std::array::from_fn(|j| s[(i, j)])))
+    }
+
+    /// Return true if the Born stability criteria are satisfied, i.e. the
+    /// stiffness matrix is positive definite.
+    ///
+    /// Reference
+    /// ---------
+    /// - Mouhat, F.; Coudert, F.-X. Phys. Rev. B 2014, 90, 224104.
+    pub fn is_born_stable(&self) -> bool {
+        self.to_matrix6().cholesky().is_some()
+    }
+
+    /// Bulk modulus in the Voigt bound.
+    pub fn bulk_modulus_voigt(&self) -> f64 {
+        let c = &self.matrix;
+        ((c[0][0] + c[1][1] + c[2][2]) + 2.0 * (c[0][1] + c[1][2] + c[0][2])) / 9.0
+    }
+
+    /// Shear modulus in the Voigt bound.
+    pub fn shear_modulus_voigt(&self) -> f64 {
+        let c = &self.matrix;
+        ((c[0][0] + c[1][1] + c[2][2]) - (c[0][1] + c[1][2] + c[0][2])
+            + 3.0 * (c[3][3] + c[4][4] + c[5][5]))
+            / 15.0
+    }
+
+    /// Bulk modulus in the Reuss bound.
+    pub fn bulk_modulus_reuss(&self) -> f64 {
+        let s = self.compliance().expect("singular elastic tensor");
+        1.0 / ((s[0][0] + s[1][1] + s[2][2]) + 2.0 * (s[0][1] + s[1][2] + s[0][2]))
+    }
+
+    /// Shear modulus in the Reuss bound.
+    pub fn shear_modulus_reuss(&self) -> f64 {
+        let s = self.compliance().expect("singular elastic tensor");
+        15.0 / (4.0 * (s[0][0] + s[1][1] + s[2][2]) - 4.0 * (s[0][1] + s[1][2] + s[0][2])
+            + 3.0 * (s[3][3] + s[4][4] + s[5][5]))
+    }
+
+    /// Bulk modulus in the Hill average.
+    pub fn bulk_modulus_hill(&self) -> f64 {
+        0.5 * (self.bulk_modulus_voigt() + self.bulk_modulus_reuss())
+    }
+
+    /// Shear modulus in the Hill average.
+    pub fn shear_modulus_hill(&self) -> f64 {
+        0.5 * (self.shear_modulus_voigt() + self.shear_modulus_reuss())
+    }
+
+    /// Young's modulus from the Hill averages.
+    pub fn youngs_modulus(&self) -> f64 {
+        let (k, g) = (self.bulk_modulus_hill(), self.shear_modulus_hill());
+        9.0 * k * g / (3.0 * k + g)
+    }
+
+    /// Poisson's ratio from the Hill averages.
+    pub fn poisson_ratio(&self) -> f64 {
+        let (k, g) = (self.bulk_modulus_hill(), self.shear_modulus_hill());
+        (3.0 * k - 2.0 * g) / (2.0 * (3.0 * k + g))
+    }
+}
+
+// symmetric 6x6 matrix from contributions with 1-based indices
+fn voigt_matrix(constant: &[(usize, usize, f64)]) -> [[f64; 6]; 6] {
+    let mut m = [[0.0; 6]; 6];
+    for &(i, j, x) in constant {
+        m[i - 1][j - 1] += x;
+        if i != j {
+            m[j - 1][i - 1] += x;
+        }
+    }
+    m
+}
+
+// linear combination of the basis matrices
+fn voigt_matrix_sum(basis: &[[[f64; 6]; 6]], values: &[f64]) -> [[f64; 6]; 6] {
+    std::array::from_fn(|i| {
+        std::array::from_fn(|j| basis.iter().zip(values).map(|(b, x)| b[i][j] * x).sum())
+    })
+}
+// d4b0e7f2 ends here
+
+// [[file:../lattice.note::3f6a92cd][3f6a92cd]]
+#[test]
+fn test_elastic_tensor() {
+    use vecfx::approx::*;
+
+    // copper in GPa
+    let (c11, c12, c44) = (168.4, 121.4, 75.4);
+    let cu = ElasticTensor::new([
+        [c11, c12, c12, 0.0, 0.0, 0.0],
+        [c12, c11, c12, 0.0, 0.0, 0.0],
+        [c12, c12, c11, 0.0, 0.0, 0.0],
+        [0.0, 0.0, 0.0, c44, 0.0, 0.0],
+        [0.0, 0.0, 0.0, 0.0, c44, 0.0],
+        [0.0, 0.0, 0.0, 0.0, 0.0, c44],
+    ]);
+    assert!(cu.is_born_stable());
+    assert_relative_eq!(
+        cu.bulk_modulus_voigt(),
+        (c11 + 2.0 * c12) / 3.0,
+        epsilon = 1e-8
+    );
+    assert_relative_eq!(
+        cu.bulk_modulus_reuss(),
+        (c11 + 2.0 * c12) / 3.0,
+        epsilon = 1e-8
+    );
+    assert_relative_eq!(
+        cu.shear_modulus_voigt(),
+        (c11 - c12 + 3.0 * c44) / 5.0,
+        epsilon = 1e-8
+    );
+    let gr = 5.0 * (c11 - c12) * c44 / (4.0 * c44 + 3.0 * (c11 - c12));
+    assert_relative_eq!(cu.shear_modulus_reuss(), gr, epsilon = 1e-8);
+
+    // stresses from linear elasticity
+    let stresses = |c: &ElasticTensor, strains: &[Strain]| {
+        let c = c.matrix();
+        strains
+            .iter()
+            .map(|s| {
+                let e = s.to_voigt();
+                std::array::from_fn(|i| (0..6).map(|j| c[i][j] * e[j]).sum())
+            })
+            .collect_vec()
+    };
+
+    let lat = Lattice::from_params(3.6, 3.6, 3.6, 90.0, 90.0, 90.0);
+    let deformations =
+        lat.elastic_deformations(CrystalSystem::Cubic, &[-0.01, -0.005, 0.005, 0.01]);
+    assert_eq!(deformations.len(), 4);
+    let strains = deformations.iter().map(|(s, _)| *s).collect_vec();
+    let fitted =
+        ElasticTensor::fit(CrystalSystem::Cubic, &strains, &stresses(&cu, &strains)).unwrap();
+    for i in 0..6 {
+        assert_relative_eq!(
+            fitted.matrix()[i].as_slice(),
+            cu.matrix()[i].as_slice(),
+            epsilon = 1e-8
+        );
+    }
+    // the strained lattice
+    let (strain, lat1) = deformations[0];
+    assert_relative_eq!(
+        lat.strain_to(&lat1, StrainMeasure::Small).matrix(),
+        strain.matrix(),
+        epsilon = 1e-12
+    );
+
+    // all constants of trigonal system from two patterns
+    let (c11, c12, c13, c14, c15, c33, c44) = (200.0, 80.0, 60.0, 15.0, 8.0, 180.0, 50.0);
+    let c66 = 0.5 * (c11 - c12);
+    let trigonal = ElasticTensor::new([
+        [c11, c12, c13, c14, c15, 0.0],
+        [c12, c11, c13, -c14, -c15, 0.0],
+        [c13, c13, c33, 0.0, 0.0, 0.0],
+        [c14, -c14, 0.0, c44, 0.0, -c15],
+        [c15, -c15, 0.0, 0.0, c44, c14],
+        [0.0, 0.0, 0.0, -c15, c14, c66],
+    ]);
+    let lat = Lattice::from_params(3.0, 3.0, 5.0, 90.0, 90.0, 120.0);
+    let system = CrystalSystem::from(lat.bravais_lattice(1e-5).unwrap());
+    assert_eq!(system, CrystalSystem::Hexagonal);
+    let strains = lat
+        .elastic_deformations(CrystalSystem::Trigonal, &[-0.01, 0.01])
+        .into_iter()
+        .map(|(s, _)| s)
+        .collect_vec();
+    let fitted = ElasticTensor::fit(
+        CrystalSystem::Trigonal,
+        &strains,
+        &stresses(&trigonal, &strains),
+    )
+    .unwrap();
+    for i in 0..6 {
+        assert_relative_eq!(
+            fitted.matrix()[i].as_slice(),
+            trigonal.matrix()[i].as_slice(),
+            epsilon = 1e-8
+        );
+    }
+    // round trip of known tensors of lower symmetry
+    let round_trip = |system: CrystalSystem, lat: &Lattice, c: &ElasticTensor| {
+        let strains = lat
+            .elastic_deformations(system, &[-0.01, 0.01])
+            .into_iter()
+            .map(|(s, _)| s)
+            .collect_vec();
+        let fitted = ElasticTensor::fit(system, &strains, &stresses(c, &strains)).unwrap();
+        for i in 0..6 {
+            assert_relative_eq!(
+                fitted.matrix()[i].as_slice(),
+                c.matrix()[i].as_slice(),
+                epsilon = 1e-8
+            );
+        }
+    };
+    // Laue class 4/m with C16 = -C26
+    let (c11, c12, c13, c16, c33, c44, c66) = (220.0, 90.0, 70.0, 12.0, 190.0, 60.0, 75.0);
+    let tetragonal = ElasticTensor::new([
+        [c11, c12, c13, 0.0, 0.0, c16],
+        [c12, c11, c13, 0.0, 0.0, -c16],
+        [c13, c13, c33, 0.0, 0.0, 0.0],
+        [0.0, 0.0, 0.0, c44, 0.0, 0.0],
+        [0.0, 0.0, 0.0, 0.0, c44, 0.0],
+        [c16, -c16, 0.0, 0.0, 0.0, c66],
+    ]);
+    let lat = Lattice::from_params(3.0, 3.0, 5.0, 90.0, 90.0, 90.0);
+    round_trip(CrystalSystem::Tetragonal, &lat, &tetragonal);
+
+    // 2-fold axis along y
+    let monoclinic = ElasticTensor::new([
+        [210.0, 85.0, 65.0, 0.0, 10.0, 0.0],
+        [85.0, 190.0, 70.0, 0.0, -6.0, 0.0],
+        [65.0, 70.0, 170.0, 0.0, 4.0, 0.0],
+        [0.0, 0.0, 0.0, 55.0, 0.0, 7.0],
+        [10.0, -6.0, 4.0, 0.0, 45.0, 0.0],
+        [0.0, 0.0, 0.0, 7.0, 0.0, 65.0],
+    ]);
+    let lat = Lattice::from_params(3.0, 4.0, 5.0, 90.0, 105.0, 90.0);
+    round_trip(CrystalSystem::Monoclinic, &lat, &monoclinic);
+
+    let triclinic = ElasticTensor::new([
+        [200.0, 80.0, 60.0, 5.0, -3.0, 2.0],
+        [80.0, 180.0, 70.0, -4.0, 6.0, 1.0],
+        [60.0, 70.0, 160.0, 3.0, 2.0, -5.0],
+        [5.0, -4.0, 3.0, 50.0, 4.0, -2.0],
+        [-3.0, 6.0, 2.0, 4.0, 45.0, 3.0],
+        [2.0, 1.0, -5.0, -2.0, 3.0, 55.0],
+    ]);
+    let lat = Lattice::from_params(3.0, 4.0, 5.0, 80.0, 95.0, 110.0);
+    round_trip(CrystalSystem::Triclinic, &lat, &triclinic);
+
+    // insufficient strains
+    assert!(ElasticTensor::fit(
+        CrystalSystem::Triclinic,
+        &strains,
+        &stresses(&trigonal, &strains)
+    )
+    .is_err());
+
+    // mechanically unstable
+    let mut c = cu.matrix();
+    c[0][1] = 200.0;
+    c[1][0] = 200.0;
+    assert!(!ElasticTensor::new(c).is_born_stable());
+}
+// 3f6a92cd ends here
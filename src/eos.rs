@@ -0,0 +1,261 @@
+// [[file:../lattice.note::6b2f0e94][6b2f0e94]]
+use crate::Lattice;
+
+use gchemol_gut::prelude::*;
+use vecfx::nalgebra::{Matrix4, Vector4};
+// 6b2f0e94 ends here
+
+// [[file:../lattice.note::f0c93d58][f0c93d58]]
+/// Conversion factor from eV/Angstrom^3 to GPa.
+pub const EV_PER_ANGSTROM3_TO_GPA: f64 = 160.21766208;
+
+impl Lattice {
+    /// Return isotropically scaled copies of the lattice with cell volumes
+    /// given in `volumes`, keeping the cell shape.
+    pub fn volume_scan(&self, volumes: &[f64]) -> Vec<Lattice> {
+        volumes
+            .iter()
+            .map(|&v| {
+                let mut lat = *self;
+                lat.set_volume(v);
+                lat
+            })
+            .collect()
+    }
+}
+
+/// Equations of state for energy-volume curves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EquationOfState {
+    /// third-order Birch-Murnaghan equation
+    BirchMurnaghan,
+    /// Murnaghan equation
+    Murnaghan,
+    /// Vinet (Rose-Vinet) equation
+    Vinet,
+}
+
+impl EquationOfState {
+    /// Return the energy at volume `v` with parameters `[E0, V0, B0, B0']`.
+    pub fn energy(&self, v: f64, params: [f64; 4]) -> f64 {
+        let [e0, v0, b0, b1] = params;
+        match self {
+            Self::BirchMurnaghan => {
+                let eta = (v0 / v).powf(2.0 / 3.0);
+                e0 + 9.0 * v0 * b0 / 16.0
+                    * ((eta - 1.0).powi(3) * b1 + (eta - 1.0).powi(2) * (6.0 - 4.0 * eta))
+            }
+            Self::Murnaghan => {
+                e0 + b0 * v / b1 * ((v0 / v).powf(b1) / (b1 - 1.0) + 1.0) - v0 * b0 / (b1 - 1.0)
+            }
+            Self::Vinet => {
+                let x = (v / v0).cbrt();
+                let eta = 1.5 * (b1 - 1.0);
+                e0 + 2.0 * b0 * v0 / (b1 - 1.0).powi(2)
+                    * (2.0 - (5.0 + 3.0 * b1 * (x - 1.0) - 3.0 * x) * (-eta * (x - 1.0)).exp())
+            }
+        }
+    }
+
+    /// Fit the equation to `energies` at `volumes` by nonlinear least
+    /// squares (Levenberg-Marquardt), starting from a parabolic fit. At
+    /// least five points are required, one more than the number of
+    /// parameters, so that the uncertainties can be estimated.
+    pub fn fit(&self, volumes: &[f64], energies: &[f64]) -> Result<EosFit> {
+        let n = volumes.len();
+        ensure!(n == energies.len(), "volumes and energies mismatch");
+        ensure!(n >= 5, "too few points for fitting: {n}");
+
+        let mut params = parabolic_guess(volumes, energies)?;
+        let residuals = |p: &Vector4<f64>| -> Vec<f64> {
+            let p = [p[0], p[1], p[2], p[3]];
+            volumes
+                .iter()
+                .zip(energies)
+                .map(|(&v, &e)| self.energy(v, p) - e)
+                .collect()
+        };
+        let jacobian = |p: &Vector4<f64>| -> Vec<Vector4<f64>> {
+            let mut cols = [vec![], vec![], vec![], vec![]];
+            for (k, col) in cols.iter_mut().enumerate() {
+                let h = 1e-6 * p[k].abs().max(1e-6);
+                let (mut p1, mut p2) = (*p, *p);
+                p1[k] += h;
+                p2[k] -= h;
+                *col = residuals(&p1)
+                    .iter()
+                    .zip(residuals(&p2))
+                    .map(|(r1, r2)| (r1 - r2) / (2.0 * h))
+                    .collect();
+            }
+            (0..n)
+                .map(|i| Vector4::new(cols[0][i], cols[1][i], cols[2][i], cols[3][i]))
+                .collect()
+        };
+        let ssr = |p: &Vector4<f64>| residuals(p).iter().map(|r| r * r).sum::<f64>();
+
+        let mut lambda = 1e-3;
+        let mut cost = ssr(&params);
+        for _ in 0..500 {
+            let jac = jacobian(&params);
+            let res = residuals(&params);
+            let jtj: Matrix4<f64> = jac.iter().map(|j| j * j.transpose()).sum();
+            let jtr: Vector4<f64> = jac.iter().zip(&res).map(|(j, r)| j * *r).sum();
+            let a = jtj + lambda * Matrix4::from_diagonal(&jtj.diagonal());
+            let Some(step) = a.lu().solve(&(-jtr)) else {
+                bail!("singular normal equations in EOS fitting");
+            };
+            let trial = params + step;
+            let trial_cost = ssr(&trial);
+            if trial_cost.is_finite() && trial_cost <= cost {
+                let converged = step
+                    .iter()
+                    .zip(params.iter())
+                    .all(|(s, p)| s.abs() <= 1e-12 * p.abs().max(1.0));
+                params = trial;
+                cost = trial_cost;
+                lambda = (lambda * 0.1).max(1e-12);
+                if converged {
+                    break;
+                }
+            } else {
+                lambda *= 10.0;
+                if lambda > 1e12 {
+                    break;
+                }
+            }
+        }
+
+        // standard errors from the covariance matrix s^2 (JᵀJ)^-1
+        let jac = jacobian(&params);
+        let jtj: Matrix4<f64> = jac.iter().map(|j| j * j.transpose()).sum();
+        let s2 = cost / (n - 4) as f64;
+        let cov = jtj
+            .try_inverse()
+            .unwrap_or_else(|| Matrix4::from_element(f64::NAN))
+            * s2;
+        let err = |k: usize| cov[(k, k)].abs().sqrt();
+        Ok(EosFit {
+            eos: *self,
+            e0: params[0],
+            v0: params[1],
+            b0: params[2],
+            b0_prime: params[3],
+            e0_err: err(0),
+            v0_err: err(1),
+            b0_err: err(2),
+            b0_prime_err: err(3),
+            rms: (cost / n as f64).sqrt(),
+        })
+    }
+}
+
+// initial [E0, V0, B0, B0'] from E = a V^2 + b V + c
+fn parabolic_guess(volumes: &[f64], energies: &[f64]) -> Result<Vector4<f64>> {
+    use vecfx::nalgebra::{DMatrix, DVector};
+
+    let n = volumes.len();
+    let a = DMatrix::from_fn(n, 3, |i, j| volumes[i].powi(2 - j as i32));
+    let b = DVector::from_column_slice(energies);
+    let x = a
+        .svd(true, true)
+        .solve(&b, 1e-14)
+        .map_err(|e| format_err!("{e}"))?;
+    let (a, b, c) = (x[0], x[1], x[2]);
+    ensure!(a > 0.0, "energies are not convex in volume");
+    let v0 = -b / (2.0 * a);
+    let e0 = a * v0 * v0 + b * v0 + c;
+    Ok(Vector4::new(e0, v0, 2.0 * a * v0, 4.0))
+}
+
+/// The result of fitting an equation of state. Energies and volumes are
+/// in the units of the input data, and bulk moduli in energy/volume.
+#[derive(Debug, Clone, Copy)]
+pub struct EosFit {
+    /// The fitted equation of state
+    pub eos: EquationOfState,
+    /// Equilibrium energy
+    pub e0: f64,
+    /// Equilibrium volume
+    pub v0: f64,
+    /// Bulk modulus
+    pub b0: f64,
+    /// Pressure derivative of bulk modulus
+    pub b0_prime: f64,
+    /// Standard error of `e0`
+    pub e0_err: f64,
+    /// Standard error of `v0`
+    pub v0_err: f64,
+    /// Standard error of `b0`
+    pub b0_err: f64,
+    /// Standard error of `b0_prime`
+    pub b0_prime_err: f64,
+    /// Root mean square of the residuals
+    pub rms: f64,
+}
+
+impl EosFit {
+    /// Return the fitted energy at volume `v`.
+    pub fn energy(&self, v: f64) -> f64 {
+        self.eos
+            .energy(v, [self.e0, self.v0, self.b0, self.b0_prime])
+    }
+
+    /// Bulk modulus in GPa, for energies in eV and volumes in Angstrom^3.
+    pub fn b0_gpa(&self) -> f64 {
+        self.b0 * EV_PER_ANGSTROM3_TO_GPA
+    }
+}
+// f0c93d58 ends here
+
+// [[file:../lattice.note::a7e05b3c][a7e05b3c]]
+#[test]
+fn test_eos_fit() {
+    use vecfx::approx::*;
+
+    let lat = Lattice::from_params(3.0, 4.0, 5.0, 80.0, 100.0, 70.0);
+    let v0 = lat.volume();
+    let volumes: Vec<_> = (0..9).map(|i| v0 * (0.92 + 0.02 * i as f64)).collect();
+    let lattices = lat.volume_scan(&volumes);
+    for (l, &v) in lattices.iter().zip(&volumes) {
+        assert_relative_eq!(l.volume(), v, epsilon = 1e-8);
+        assert_relative_eq!(
+            l.angles().as_slice(),
+            lat.angles().as_slice(),
+            epsilon = 1e-8
+        );
+    }
+
+    // B0 = 100 GPa, B0' = 4.5
+    let params = [-10.0, v0, 100.0 / EV_PER_ANGSTROM3_TO_GPA, 4.5];
+    for eos in [
+        EquationOfState::BirchMurnaghan,
+        EquationOfState::Murnaghan,
+        EquationOfState::Vinet,
+    ] {
+        let energies: Vec<_> = volumes.iter().map(|&v| eos.energy(v, params)).collect();
+        let fit = eos.fit(&volumes, &energies).unwrap();
+        assert_relative_eq!(fit.e0, -10.0, epsilon = 1e-8);
+        assert_relative_eq!(fit.v0, v0, epsilon = 1e-6);
+        assert_relative_eq!(fit.b0_gpa(), 100.0, epsilon = 1e-4);
+        assert_relative_eq!(fit.b0_prime, 4.5, epsilon = 1e-4);
+        assert!(fit.rms < 1e-8);
+        // the equilibrium is the minimum
+        assert!(fit.energy(v0 * 1.01) > fit.e0 && fit.energy(v0 * 0.99) > fit.e0);
+    }
+
+    // noisy data: consistent parameters with finite uncertainties
+    let bm = EquationOfState::BirchMurnaghan;
+    let energies: Vec<_> = volumes
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| bm.energy(v, params) + 1e-4 * (i as f64 * 2.3).sin())
+        .collect();
+    let fit = EquationOfState::Vinet.fit(&volumes, &energies).unwrap();
+    assert!(fit.v0_err > 0.0 && fit.b0_err > 0.0 && fit.b0_prime_err > 0.0);
+    assert!((fit.v0 - v0).abs() < 3.0 * fit.v0_err + 1e-2);
+    assert!((fit.b0_gpa() - 100.0).abs() < 2.0);
+
+    assert!(bm.fit(&volumes[..4], &energies[..4]).is_err());
+}
+// a7e05b3c ends here
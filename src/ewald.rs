@@ -0,0 +1,298 @@
+// [[file:../lattice.note::8b1e5d0a][8b1e5d0a]]
+use crate::utils::*;
+use crate::Lattice;
+
+use std::f64::consts::PI;
+use vecfx::*;
+// 8b1e5d0a ends here
+
+// [[file:../lattice.note::e2c7f943][e2c7f943]]
+/// Coulomb constant e^2/(4πε0) in eV Angstrom.
+pub const COULOMB_CONSTANT: f64 = 14.399645;
+
+/// Ewald summation of point charges in a periodic lattice.
+///
+/// The total electrostatic energy is split into a short-ranged real space
+/// sum, a long-ranged reciprocal space sum, the self energy and the energy
+/// of the neutralizing background for charged cells.
+///
+/// Reference
+/// ---------
+/// - Frenkel, D.; Smit, B. Understanding Molecular Simulation, 2nd ed.;
+///   Academic Press: San Diego, 2002.
+/// - Perram, J. W.; Petersen, H. G.; de Leeuw, S. W. Mol. Phys. 1988, 65,
+///   875-893.
+#[derive(Debug, Clone, Copy)]
+pub struct Ewald {
+    alpha: f64,
+    r_cut: f64,
+    g_cut: f64,
+}
+
+/// Results of Ewald summation, in eV and Angstrom.
+#[derive(Debug, Clone)]
+pub struct EwaldResult {
+    /// electrostatic energy in eV
+    pub energy: f64,
+    /// forces on each charge in eV/Angstrom
+    pub forces: Vec<[f64; 3]>,
+    /// stress tensor `σ = 1/V ∂E/∂ε` in eV/Angstrom^3. The pressure is `-tr(σ)/3`.
+    pub stress: Matrix3f,
+}
+
+impl Ewald {
+    /// Choose the Ewald parameters automatically for `n` charges in
+    /// `lattice`, with the terms neglected in both real and reciprocal
+    /// space sums of relative magnitude `accuracy` (e.g. 1e-6).
+    ///
+    /// The splitting parameter `α = √π (n/V^2)^(1/6)` balances the cost of
+    /// both sums. The cutoffs are `r_cut = p/α` and `g_cut = 2 α p` with
+    /// `p = √(-ln(accuracy))`.
+    pub fn new(lattice: &Lattice, n: usize, accuracy: f64) -> Self {
+        assert!(
+            accuracy > 0.0 && accuracy < 1.0,
+            "invalid accuracy: {accuracy}"
+        );
+        let v = lattice.volume().abs();
+        let alpha = PI.sqrt() * (n.max(1) as f64 / (v * v)).powf(1.0 / 6.0);
+        let p = (-accuracy.ln()).sqrt();
+        Self::from_params(alpha, p / alpha, 2.0 * alpha * p)
+    }
+
+    /// Construct with splitting parameter `alpha` (in 1/Angstrom), real
+    /// space cutoff `r_cut` (in Angstrom) and reciprocal space cutoff
+    /// `g_cut` (in 1/Angstrom).
+    pub fn from_params(alpha: f64, r_cut: f64, g_cut: f64) -> Self {
+        assert!(alpha > 0.0, "invalid splitting parameter: {alpha}");
+        Self {
+            alpha,
+            r_cut,
+            g_cut,
+        }
+    }
+
+    /// The splitting parameter in 1/Angstrom.
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// The real space cutoff in Angstrom.
+    pub fn r_cut(&self) -> f64 {
+        self.r_cut
+    }
+
+    /// The reciprocal space cutoff in 1/Angstrom.
+    pub fn g_cut(&self) -> f64 {
+        self.g_cut
+    }
+
+    /// Compute the electrostatic energy, forces and stress for point
+    /// `charges` (in e) at Cartesian `positions` in `lattice`.
+    pub fn compute(
+        &self,
+        lattice: &Lattice,
+        positions: &[[f64; 3]],
+        charges: &[f64],
+    ) -> EwaldResult {
+        assert_eq!(
+            positions.len(),
+            charges.len(),
+            "positions and charges mismatch"
+        );
+        let n = positions.len();
+        let alpha = self.alpha;
+        let volume = lattice.volume().abs();
+        let positions: Vec<_> = positions.iter().map(|&p| Vector3f::from(p)).collect();
+
+        let mut energy = 0.0;
+        let mut forces = vec![Vector3f::zeros(); n];
+        let mut virial = Matrix3f::zeros();
+
+        // real space sum over all relevant periodic images
+        let [na, nb, nc] = lattice.n_min_images(self.r_cut);
+        let images: Vec<_> = lattice
+            .replicate(-na..=na, -nb..=nb, -nc..=nc)
+            .map(|image| lattice.matrix() * image)
+            .collect();
+        let r_cut2 = self.r_cut * self.r_cut;
+        for i in 0..n {
+            for j in i..n {
+                let qij = charges[i] * charges[j];
+                // the same pair in different images are counted twice, except
+                // for the self interaction
+                let scale = if i == j { 0.5 } else { 1.0 };
+                // fold the pair vector into [-0.5, 0.5] in fractional coordinates
+                let f = lattice.inv_matrix() * (positions[i] - positions[j]);
+                let d = lattice.matrix() * f.map(|x| x - x.round());
+                for t in images.iter() {
+                    let r = d + t;
+                    let r2 = r.norm_squared();
+                    if r2 > r_cut2 || r2 < 1e-16 {
+                        continue;
+                    }
+                    let rn = r2.sqrt();
+                    let e = erfc(alpha * rn) / rn;
+                    // -dφ/dr
+                    let de = e / rn + 2.0 * alpha / PI.sqrt() * (-alpha * alpha * r2).exp() / rn;
+                    energy += scale * qij * e;
+                    let f = qij * de / rn * r;
+                    forces[i] += f;
+                    forces[j] -= f;
+                    virial -= scale * qij * de / rn * r * r.transpose();
+                }
+            }
+        }
+
+        // reciprocal space sum
+        let k = 2.0 * PI / volume;
+        for g in lattice.g_vectors(self.g_cut).into_iter().skip(1) {
+            let g = g.g;
+            let g2 = g.norm_squared();
+            let phases: Vec<_> = positions.iter().map(|r| g.dot(r)).collect();
+            let s_cos: f64 = phases.iter().zip(charges).map(|(p, q)| q * p.cos()).sum();
+            let s_sin: f64 = phases.iter().zip(charges).map(|(p, q)| q * p.sin()).sum();
+            let s2 = s_cos * s_cos + s_sin * s_sin;
+            let f = (-g2 / (4.0 * alpha * alpha)).exp() / g2;
+            energy += k * f * s2;
+            for i in 0..n {
+                let x = s_cos * phases[i].sin() - s_sin * phases[i].cos();
+                forces[i] += 2.0 * k * f * charges[i] * x * g;
+            }
+            let m = g * g.transpose() * 2.0 * (1.0 / (4.0 * alpha * alpha) + 1.0 / g2);
+            virial += k * f * s2 * (m - Matrix3f::identity());
+        }
+
+        // self energy and neutralizing background for charged cell
+        let q2: f64 = charges.iter().map(|q| q * q).sum();
+        energy -= alpha / PI.sqrt() * q2;
+        let q: f64 = charges.iter().sum();
+        let e_bg = -PI * q * q / (2.0 * volume * alpha * alpha);
+        energy += e_bg;
+        virial -= e_bg * Matrix3f::identity();
+
+        EwaldResult {
+            energy: energy * COULOMB_CONSTANT,
+            forces: forces
+                .into_iter()
+                .map(|f| (f * COULOMB_CONSTANT).into())
+                .collect(),
+            stress: virial * COULOMB_CONSTANT / volume,
+        }
+    }
+}
+
+impl Lattice {
+    /// Compute the electrostatic energy, forces and stress for point
+    /// `charges` at Cartesian `positions` using Ewald summation with
+    /// parameters chosen for target `accuracy`. See [`Ewald::new`].
+    pub fn ewald_sum(&self, positions: &[[f64; 3]], charges: &[f64], accuracy: f64) -> EwaldResult {
+        Ewald::new(self, positions.len(), accuracy).compute(self, positions, charges)
+    }
+}
+// e2c7f943 ends here
+
+// [[file:../lattice.note::1c7a3f58][1c7a3f58]]
+#[test]
+fn test_erfc() {
+    use approx::*;
+
+    assert_relative_eq!(erfc(0.0), 1.0, max_relative = 1e-14);
+    assert_relative_eq!(erfc(0.5), 0.4795001221869535, max_relative = 1e-13);
+    assert_relative_eq!(erfc(1.0), 0.15729920705028513, max_relative = 1e-13);
+    assert_relative_eq!(erfc(2.4), 6.885138966450786e-4, max_relative = 1e-12);
+    assert_relative_eq!(erfc(2.6), 2.360344165293492e-4, max_relative = 1e-12);
+    assert_relative_eq!(erfc(3.0), 2.209049699858544e-5, max_relative = 1e-12);
+    assert_relative_eq!(erfc(5.0), 1.537459794428035e-12, max_relative = 1e-12);
+    assert_relative_eq!(erfc(-1.0), 1.8427007929497148, max_relative = 1e-13);
+}
+
+#[test]
+fn test_ewald_madelung() {
+    use approx::*;
+
+    // NaCl: fcc with nearest neighbor distance of 1
+    let lat = Lattice::new([[0.0, 1.0, 1.0], [1.0, 0.0, 1.0], [1.0, 1.0, 0.0]]);
+    let r = lat.ewald_sum(&[[0.0; 3], [1.0, 0.0, 0.0]], &[1.0, -1.0], 1e-10);
+    assert_relative_eq!(-r.energy / COULOMB_CONSTANT, 1.747565, epsilon = 1e-6);
+
+    // the same crystal in a left-handed basis
+    let lat = Lattice::new([[0.0, 1.0, 1.0], [1.0, 1.0, 0.0], [1.0, 0.0, 1.0]]);
+    assert!(lat.volume() < 0.0);
+    let r = lat.ewald_sum(&[[0.0; 3], [1.0, 0.0, 0.0]], &[1.0, -1.0], 1e-10);
+    assert_relative_eq!(-r.energy / COULOMB_CONSTANT, 1.747565, epsilon = 1e-6);
+
+    // CsCl: nearest neighbor distance is √3/2
+    let lat = Lattice::from_params(1.0, 1.0, 1.0, 90.0, 90.0, 90.0);
+    let r = lat.ewald_sum(&[[0.0; 3], [0.5; 3]], &[1.0, -1.0], 1e-10);
+    let r0 = 3f64.sqrt() / 2.0;
+    assert_relative_eq!(-r.energy / COULOMB_CONSTANT * r0, 1.762675, epsilon = 1e-6);
+
+    // zincblende: nearest neighbor distance is √3/4
+    let lat = Lattice::new([[0.0, 0.5, 0.5], [0.5, 0.0, 0.5], [0.5, 0.5, 0.0]]);
+    let r = lat.ewald_sum(&[[0.0; 3], [0.25; 3]], &[1.0, -1.0], 1e-10);
+    let r0 = 3f64.sqrt() / 4.0;
+    assert_relative_eq!(-r.energy / COULOMB_CONSTANT * r0, 1.638055, epsilon = 1e-6);
+
+    // forces vanish by symmetry
+    for f in r.forces {
+        assert_relative_eq!(Vector3f::from(f), Vector3f::zeros(), epsilon = 1e-8);
+    }
+}
+
+#[test]
+fn test_ewald_derivatives() {
+    use approx::*;
+
+    let lat = Lattice::from_params(4.0, 4.5, 5.0, 80.0, 100.0, 110.0);
+    let positions = [
+        [0.1, 0.2, 0.3],
+        [1.5, 2.0, 1.0],
+        [3.0, 0.5, 2.5],
+        [0.5, 3.5, 4.0],
+    ];
+    let charges = [1.0, -0.5, 0.8, -0.6];
+
+    // independent of the splitting parameter
+    let ewald = Ewald::new(&lat, positions.len(), 1e-12);
+    let r = ewald.compute(&lat, &positions, &charges);
+    let p = (-1e-12f64.ln()).sqrt();
+    let alpha = 1.5 * ewald.alpha();
+    let r2 =
+        Ewald::from_params(alpha, p / alpha, 2.0 * alpha * p).compute(&lat, &positions, &charges);
+    assert_relative_eq!(r.energy, r2.energy, epsilon = 1e-8);
+
+    // forces by finite difference
+    let h = 1e-5;
+    for i in 0..positions.len() {
+        for k in 0..3 {
+            let mut p1 = positions;
+            let mut p2 = positions;
+            p1[i][k] += h;
+            p2[i][k] -= h;
+            let e1 = ewald.compute(&lat, &p1, &charges).energy;
+            let e2 = ewald.compute(&lat, &p2, &charges).energy;
+            assert_relative_eq!(-(e1 - e2) / (2.0 * h), r.forces[i][k], epsilon = 1e-5);
+        }
+    }
+
+    // stress by finite difference under homogeneous strain
+    let strained = |eps: Matrix3f| {
+        let f = Matrix3f::identity() + eps;
+        let lat = Lattice::from_matrix(f * lat.matrix());
+        let positions: Vec<[f64; 3]> = positions
+            .iter()
+            .map(|&p| (f * Vector3f::from(p)).into())
+            .collect();
+        ewald.compute(&lat, &positions, &charges).energy
+    };
+    let v = lat.volume();
+    for (a, b) in [(0, 0), (1, 1), (2, 2), (0, 1), (1, 2), (0, 2)] {
+        let mut eps = Matrix3f::zeros();
+        eps[(a, b)] = h;
+        eps[(b, a)] = h;
+        let de = (strained(eps) - strained(-eps)) / (2.0 * h);
+        let scale = if a == b { 1.0 } else { 2.0 };
+        assert_relative_eq!(de / v / scale, r.stress[(a, b)], epsilon = 1e-5);
+    }
+}
+// 1c7a3f58 ends here
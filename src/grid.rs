@@ -0,0 +1,290 @@
+// [[file:../lattice.note::c6e1a4f8][c6e1a4f8]]
+use crate::Lattice;
+
+use gchemol_gut::prelude::*;
+use vecfx::*;
+// c6e1a4f8 ends here
+
+// [[file:../lattice.note::7a5d3e92][7a5d3e92]]
+/// Volumetric data on a periodic grid, such as charge densities from
+/// CHGCAR or Gaussian cube files.
+///
+/// Grid point (i, j, k) is located at fractional coordinates (i/nx, j/ny,
+/// k/nz) of the lattice, and stored at flat index `(i * ny + j) * nz + k`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PeriodicGrid {
+    lattice: Lattice,
+    dims: [usize; 3],
+    data: Vec<f64>,
+}
+
+// Catmull-Rom cubic convolution weights for points at -1, 0, 1, 2 relative
+// to the lower grid point, with `t` in [0, 1).
+fn cubic_weights(t: f64) -> [f64; 4] {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    [
+        0.5 * (-t3 + 2.0 * t2 - t),
+        0.5 * (3.0 * t3 - 5.0 * t2 + 2.0),
+        0.5 * (-3.0 * t3 + 4.0 * t2 + t),
+        0.5 * (t3 - t2),
+    ]
+}
+
+impl PeriodicGrid {
+    /// Construct from `lattice`, grid dimensions `dims` and grid values
+    /// `data` in the layout described above.
+    pub fn new(lattice: Lattice, dims: [usize; 3], data: Vec<f64>) -> Self {
+        assert!(
+            dims.iter().all(|&n| n > 0),
+            "invalid grid dimensions: {dims:?}"
+        );
+        assert_eq!(
+            data.len(),
+            dims.iter().product::<usize>(),
+            "invalid grid data size"
+        );
+        Self {
+            lattice,
+            dims,
+            data,
+        }
+    }
+
+    /// Construct a grid with all values being zero.
+    pub fn zeros(lattice: Lattice, dims: [usize; 3]) -> Self {
+        Self::new(lattice, dims, vec![0.0; dims.iter().product()])
+    }
+
+    /// Construct the volumetric density of point `values` at Cartesian
+    /// `positions`, spread with B-splines of `order`. See
+    /// [`Lattice::spread_to_grid`].
+    pub fn from_points(
+        lattice: Lattice,
+        dims: [usize; 3],
+        order: usize,
+        positions: &[[f64; 3]],
+        values: &[f64],
+    ) -> Self {
+        let mut grid = Self::new(
+            lattice,
+            dims,
+            lattice.spread_to_grid(dims, order, positions, values),
+        );
+        let dv = grid.voxel_volume();
+        grid.data.iter_mut().for_each(|x| *x /= dv);
+        grid
+    }
+
+    /// The lattice of the grid.
+    pub fn lattice(&self) -> &Lattice {
+        &self.lattice
+    }
+
+    /// Grid dimensions: nx, ny, nz
+    pub fn dims(&self) -> [usize; 3] {
+        self.dims
+    }
+
+    /// Grid values in flat layout.
+    pub fn data(&self) -> &[f64] {
+        &self.data
+    }
+
+    /// Mutable grid values in flat layout.
+    pub fn data_mut(&mut self) -> &mut [f64] {
+        &mut self.data
+    }
+
+    /// Return the value at grid point (`i`, `j`, `k`), obeying the periodic
+    /// boundary conditions.
+    pub fn get(&self, i: isize, j: isize, k: isize) -> f64 {
+        self.data[self.index(i, j, k)]
+    }
+
+    fn index(&self, i: isize, j: isize, k: isize) -> usize {
+        let [nx, ny, nz] = self.dims.map(|n| n as isize);
+        let (i, j, k) = (i.rem_euclid(nx), j.rem_euclid(ny), k.rem_euclid(nz));
+        ((i * ny + j) * nz + k) as usize
+    }
+
+    /// The volume of each grid voxel.
+    pub fn voxel_volume(&self) -> f64 {
+        self.lattice.volume().abs() / self.data.len() as f64
+    }
+
+    /// Integrate the volumetric data over the unit cell.
+    pub fn integrate(&self) -> f64 {
+        self.data.iter().sum::<f64>() * self.voxel_volume()
+    }
+
+    // Return the lower grid point and the offset in grid units for point
+    // `p` in Cartesian coordinates.
+    fn locate<T: Into<Vector3f>>(&self, p: T) -> ([isize; 3], [f64; 3]) {
+        let f = self.lattice.wrap_frac(self.lattice.to_frac(p));
+        let u: [f64; 3] = std::array::from_fn(|i| f[i] * self.dims[i] as f64);
+        (u.map(|x| x.floor() as isize), u.map(|x| x - x.floor()))
+    }
+
+    /// Interpolate the grid values at Cartesian point `p` using trilinear
+    /// interpolation.
+    pub fn interpolate_trilinear<T: Into<Vector3f>>(&self, p: T) -> f64 {
+        let ([i, j, k], [tx, ty, tz]) = self.locate(p);
+        let mut v = 0.0;
+        for (di, wx) in [(0, 1.0 - tx), (1, tx)] {
+            for (dj, wy) in [(0, 1.0 - ty), (1, ty)] {
+                for (dk, wz) in [(0, 1.0 - tz), (1, tz)] {
+                    v += wx * wy * wz * self.get(i + di, j + dj, k + dk);
+                }
+            }
+        }
+        v
+    }
+
+    /// Interpolate the grid values at Cartesian point `p` using tricubic
+    /// (Catmull-Rom) interpolation, which passes through the grid values
+    /// and has continuous first derivatives.
+    pub fn interpolate_tricubic<T: Into<Vector3f>>(&self, p: T) -> f64 {
+        let ([i, j, k], [tx, ty, tz]) = self.locate(p);
+        let [wx, wy, wz] = [tx, ty, tz].map(cubic_weights);
+        let mut v = 0.0;
+        for (di, a) in wx.iter().enumerate() {
+            for (dj, b) in wy.iter().enumerate() {
+                for (dk, c) in wz.iter().enumerate() {
+                    let (di, dj, dk) = (di as isize - 1, dj as isize - 1, dk as isize - 1);
+                    v += a * b * c * self.get(i + di, j + dj, k + dk);
+                }
+            }
+        }
+        v
+    }
+
+    /// Return the average over planes spanned by the other two lattice
+    /// vectors, as a profile along lattice direction `axis` (0, 1 or 2).
+    /// Point `i` of the profile lies at distance `i / n * w` along the
+    /// plane normal, with `w` the perpendicular width of the cell on this
+    /// direction (see [`Lattice::widths`]).
+    pub fn planar_average(&self, axis: usize) -> Vec<f64> {
+        assert!(axis < 3, "invalid axis: {axis}");
+        let [nx, ny, nz] = self.dims;
+        let n = self.dims[axis];
+        let mut profile = vec![0.0; n];
+        for i in 0..nx {
+            for j in 0..ny {
+                for k in 0..nz {
+                    profile[[i, j, k][axis]] += self.data[(i * ny + j) * nz + k];
+                }
+            }
+        }
+        let m = (self.data.len() / n) as f64;
+        profile.iter_mut().for_each(|x| *x /= m);
+        profile
+    }
+
+    /// Return the average along lattice direction `axis` (0, 1 or 2), as a
+    /// 2D map over the other two directions in row-major order. For `axis`
+    /// = 2, the result has `nx * ny` values with index `i * ny + j`.
+    pub fn line_average(&self, axis: usize) -> Vec<f64> {
+        assert!(axis < 3, "invalid axis: {axis}");
+        let [nx, ny, nz] = self.dims;
+        let [_, n2] = match axis {
+            0 => [ny, nz],
+            1 => [nx, nz],
+            _ => [nx, ny],
+        };
+        let mut map = vec![0.0; self.data.len() / self.dims[axis]];
+        for i in 0..nx {
+            for j in 0..ny {
+                for k in 0..nz {
+                    let [a, b] = match axis {
+                        0 => [j, k],
+                        1 => [i, k],
+                        _ => [i, j],
+                    };
+                    map[a * n2 + b] += self.data[(i * ny + j) * nz + k];
+                }
+            }
+        }
+        let m = self.dims[axis] as f64;
+        map.iter_mut().for_each(|x| *x /= m);
+        map
+    }
+}
+// 7a5d3e92 ends here
+
+// [[file:../lattice.note::29b4f06d][29b4f06d]]
+#[test]
+fn test_periodic_grid() {
+    use std::f64::consts::PI;
+    use vecfx::approx::*;
+
+    let lat = Lattice::from_params(5.0, 6.0, 7.0, 80.0, 100.0, 110.0);
+    let dims = [20, 24, 28];
+    let [nx, ny, nz] = dims;
+    let func = |f: Vector3f| 1.0 + (2.0 * PI * f.x).cos() + 0.5 * (2.0 * PI * f.z).sin();
+    let mut grid = PeriodicGrid::zeros(lat, dims);
+    for i in 0..nx {
+        for j in 0..ny {
+            for k in 0..nz {
+                let f = Vector3f::from([
+                    i as f64 / nx as f64,
+                    j as f64 / ny as f64,
+                    k as f64 / nz as f64,
+                ]);
+                grid.data_mut()[(i * ny + j) * nz + k] = func(f);
+            }
+        }
+    }
+    assert_relative_eq!(grid.integrate(), lat.volume(), epsilon = 1e-8);
+    assert_eq!(grid.get(-1, 0, 0), grid.get(19, 0, 0));
+
+    // exact at grid points
+    let p = lat.to_cart([0.25, 0.5, 0.5]);
+    assert_relative_eq!(
+        grid.interpolate_trilinear(p),
+        grid.get(5, 12, 14),
+        epsilon = 1e-8
+    );
+    assert_relative_eq!(
+        grid.interpolate_tricubic(p),
+        grid.get(5, 12, 14),
+        epsilon = 1e-8
+    );
+    // off grid points
+    let f = Vector3f::from([0.313, 0.527, 1.771]);
+    let p = lat.to_cart(f);
+    let v = func(f);
+    let v1 = grid.interpolate_trilinear(p);
+    let v3 = grid.interpolate_tricubic(p);
+    assert!((v3 - v).abs() < (v1 - v).abs());
+    assert_relative_eq!(v3, v, epsilon = 1e-3);
+
+    // planar and line averages
+    let profile = grid.planar_average(0);
+    assert_eq!(profile.len(), nx);
+    assert_relative_eq!(profile[0], 2.0, epsilon = 1e-8);
+    assert_relative_eq!(profile[10], 0.0, epsilon = 1e-8);
+    let profile = grid.planar_average(1);
+    assert_relative_eq!(profile[7], 1.0, epsilon = 1e-8);
+    let map = grid.line_average(2);
+    assert_eq!(map.len(), nx * ny);
+    assert_relative_eq!(map[3], 2.0, epsilon = 1e-8);
+    let map = grid.line_average(0);
+    assert_eq!(map.len(), ny * nz);
+    assert_relative_eq!(
+        map[7],
+        1.0 + 0.5 * (2.0 * PI * 7.0 / nz as f64).sin(),
+        epsilon = 1e-8
+    );
+
+    // density from point charges
+    let grid = PeriodicGrid::from_points(
+        lat,
+        dims,
+        6,
+        &[[0.1, 0.2, 0.3], [2.0, 3.0, 1.0]],
+        &[1.0, 2.0],
+    );
+    assert_relative_eq!(grid.integrate(), 3.0, epsilon = 1e-8);
+}
+// 29b4f06d ends here
@@ -0,0 +1,129 @@
+// [[file:../lattice.note::6d3e1b58][6d3e1b58]]
+use crate::utils::*;
+use crate::Lattice;
+
+use std::collections::HashSet;
+// 6d3e1b58 ends here
+
+// [[file:../lattice.note::a4c97f20][a4c97f20]]
+/// Return the Hermite normal form of a nonsingular integer matrix whose
+/// rows span a lattice. The result is lower triangular with positive
+/// diagonal elements, and spans the same lattice.
+pub fn hermite_normal_form(m: [[isize; 3]; 3]) -> [[isize; 3]; 3] {
+    assert_ne!(imatrix_det(m), 0, "singular matrix: {m:?}");
+    let mut h = m;
+    let sub_row = |h: &mut [[isize; 3]; 3], r: usize, i: usize, q: isize| {
+        let row = h[i];
+        h[r].iter_mut().zip(row).for_each(|(x, y)| *x -= q * y);
+    };
+    // eliminate upper right elements column by column using Euclid's algorithm
+    for r in (0..3).rev() {
+        for i in 0..r {
+            while h[i][r] != 0 {
+                let q = h[r][r] / h[i][r];
+                sub_row(&mut h, r, i, q);
+                h.swap(i, r);
+            }
+        }
+        if h[r][r] < 0 {
+            h[r] = h[r].map(|x| -x);
+        }
+    }
+    // reduce lower left elements modulo the diagonal elements
+    for r in 1..3 {
+        for c in (0..r).rev() {
+            let q = h[r][c].div_euclid(h[c][c]);
+            sub_row(&mut h, r, c, q);
+        }
+    }
+    h
+}
+
+/// Return an iterator over all Hermite normal form matrices with
+/// determinant `n`. Each matrix has the form
+///
+/// ```text
+/// [[a, 0, 0],
+///  [b, c, 0],
+///  [d, e, f]]
+/// ```
+///
+/// with `a c f = n`, `0 <= b < a`, `0 <= d < a` and `0 <= e < c`. The rows
+/// are the supercell vectors in the convention of [`Lattice::supercell`].
+pub fn hermite_normal_forms(n: usize) -> impl Iterator<Item = [[isize; 3]; 3]> {
+    let n = n as isize;
+    let divisors = move |n: isize| (1..=n).filter(move |i| n % i == 0);
+    divisors(n).flat_map(move |a| {
+        divisors(n / a).flat_map(move |c| {
+            let f = n / a / c;
+            (0..a).flat_map(move |b| {
+                (0..a).flat_map(move |d| (0..c).map(move |e| [[a, 0, 0], [b, c, 0], [d, e, f]]))
+            })
+        })
+    })
+}
+
+impl Lattice {
+    /// Return an iterator over all distinct superlattices of index `n`,
+    /// given as Hermite normal form matrices (see
+    /// [`hermite_normal_forms`]) and the corresponding supercell
+    /// lattices.
+    ///
+    /// If `symprec` is set, superlattices equivalent under the lattice
+    /// point-group operations are removed, keeping only the first one of
+    /// each class. `symprec` is the tolerance passed to
+    /// [`Lattice::point_group_operations`].
+    pub fn superlattices(
+        &self,
+        n: usize,
+        symprec: Option<f64>,
+    ) -> impl Iterator<Item = ([[isize; 3]; 3], Lattice)> + '_ {
+        let ops = symprec.map(|tol| self.point_group_operations(tol));
+        let mut seen = HashSet::new();
+        hermite_normal_forms(n)
+            .filter(move |&h| match &ops {
+                Some(ops) => {
+                    if seen.contains(&h) {
+                        return false;
+                    }
+                    // rotated supercell vectors: row r => w r
+                    for &w in ops {
+                        seen.insert(hermite_normal_form(imatrix_mul(h, imatrix_transpose(w))));
+                    }
+                    true
+                }
+                None => true,
+            })
+            .map(|h| (h, self.supercell(h)))
+    }
+}
+// a4c97f20 ends here
+
+// [[file:../lattice.note::58e0c9d7][58e0c9d7]]
+#[test]
+fn test_hnf() {
+    let counts: Vec<_> = (1..=6).map(|n| hermite_normal_forms(n).count()).collect();
+    assert_eq!(counts, [1, 7, 13, 35, 31, 91]);
+
+    for h in hermite_normal_forms(4) {
+        assert_eq!(hermite_normal_form(h), h);
+    }
+    let m = [[1, 2, 0], [-1, 0, 3], [2, 1, 1]];
+    let h = hermite_normal_form(m);
+    assert_eq!(h, [[11, 0, 0], [6, 1, 0], [7, 0, 1]]);
+    // the same lattice: m = u h with u unimodular
+    let u = imatrix_to_matrix3f(m) * imatrix_to_matrix3f(h).try_inverse().unwrap();
+    assert_eq!(imatrix_det(matrix3f_to_imatrix(u)), 1);
+    assert!((u - u.map(f64::round)).amax() < 1e-8);
+
+    // symmetry distinct superlattices
+    let lat = Lattice::from_params(4.0, 4.0, 4.0, 90.0, 90.0, 90.0);
+    assert_eq!(lat.superlattices(2, Some(1e-3)).count(), 3);
+    assert_eq!(lat.superlattices(2, None).count(), 7);
+    let lat = Lattice::new([[0.0, 2.0, 2.0], [2.0, 0.0, 2.0], [2.0, 2.0, 0.0]]);
+    let counts: Vec<_> = (2..=4)
+        .map(|n| lat.superlattices(n, Some(1e-3)).count())
+        .collect();
+    assert_eq!(counts, [2, 3, 7]);
+}
+// 58e0c9d7 ends here
@@ -0,0 +1,1069 @@
+// [[file:../lattice.note::5d8c2e17][5d8c2e17]]
+use crate::utils::*;
+use crate::Lattice;
+
+use gchemol_gut::prelude::*;
+use vecfx::*;
+// 5d8c2e17 ends here
+
+// [[file:../lattice.note::b71f4a90][b71f4a90]]
+/// Bravais lattice types and variations in the convention of Setyawan and
+/// Curtarolo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BravaisLattice {
+    /// simple cubic
+    Cub,
+    /// face-centered cubic
+    Fcc,
+    /// body-centered cubic
+    Bcc,
+    /// simple tetragonal
+    Tet,
+    /// body-centered tetragonal with c < a
+    Bct1,
+    /// body-centered tetragonal with c > a
+    Bct2,
+    /// simple orthorhombic
+    Orc,
+    /// face-centered orthorhombic with 1/a² > 1/b² + 1/c²
+    Orcf1,
+    /// face-centered orthorhombic with 1/a² < 1/b² + 1/c²
+    Orcf2,
+    /// face-centered orthorhombic with 1/a² = 1/b² + 1/c²
+    Orcf3,
+    /// body-centered orthorhombic
+    Orci,
+    /// C-centered orthorhombic
+    Orcc,
+    /// hexagonal
+    Hex,
+    /// rhombohedral with α < 90°
+    Rhl1,
+    /// rhombohedral with α > 90°
+    Rhl2,
+    /// simple monoclinic
+    Mcl,
+    /// C-centered monoclinic with kγ > 90°
+    Mclc1,
+    /// C-centered monoclinic with kγ = 90°
+    Mclc2,
+    /// C-centered monoclinic with kγ < 90° and b cos α / c + b² sin² α / a²
+    /// < 1
+    Mclc3,
+    /// C-centered monoclinic with kγ < 90° and b cos α / c + b² sin² α / a²
+    /// = 1
+    Mclc4,
+    /// C-centered monoclinic with kγ < 90° and b cos α / c + b² sin² α / a²
+    /// > 1
+    Mclc5,
+    /// triclinic with all reciprocal angles > 90°
+    Tri1a,
+    /// triclinic with all reciprocal angles < 90°
+    Tri1b,
+    /// triclinic with reciprocal angles kα, kβ > 90° and kγ = 90°. The
+    /// variation with kα, kβ < 90° (TRI2b) is the same lattice with the
+    /// third reciprocal vector reversed, and is always given in this
+    /// setting.
+    Tri2a,
+}
+
+// The standard primitive cell: rows of `prim` are the primitive vectors in
+// the basis of the lattice it was derived from. `params` holds a, b, c of
+// the conventional cell and the angle α in degrees.
+struct StandardCell {
+    bravais: BravaisLattice,
+    prim: [[isize; 3]; 3],
+    params: [f64; 4],
+}
+
+// the order of a proper rotation, or None for improper ones
+fn rotation_order(w: [[isize; 3]; 3]) -> Option<usize> {
+    if imatrix_det(w) != 1 {
+        return None;
+    }
+    match w[0][0] + w[1][1] + w[2][2] {
+        -1 => Some(2),
+        0 => Some(3),
+        1 => Some(4),
+        2 => Some(6),
+        _ => None,
+    }
+}
+
+// The shortest lattice vector along the rotation axis of `w`, with its
+// first nonzero element positive.
+fn rotation_axis(w: [[isize; 3]; 3], order: usize) -> [isize; 3] {
+    for i in 0..3 {
+        // sum over the cyclic group projects onto the axis
+        let mut v = [0; 3];
+        v[i] = 1;
+        let mut u = [0; 3];
+        for _ in 0..order {
+            (0..3).for_each(|j| u[j] += v[j]);
+            v = imatrix_mul_vec(w, v);
+        }
+        let g = u.iter().fold(0, |g, &x| gcd(g, x));
+        if g != 0 {
+            let s = u.iter().find(|&&x| x != 0).unwrap().signum();
+            return u.map(|x| s * x / g);
+        }
+    }
+    unreachable!()
+}
+
+// Distinct rotation axes of given order, together with one rotation about
+// each of them.
+fn rotation_axes(ops: &[[[isize; 3]; 3]], order: usize) -> Vec<([isize; 3], [[isize; 3]; 3])> {
+    let mut axes: Vec<([isize; 3], [[isize; 3]; 3])> = vec![];
+    for &w in ops {
+        if rotation_order(w) == Some(order) {
+            let u = rotation_axis(w, order);
+            if axes.iter().all(|(x, _)| *x != u) {
+                axes.push((u, w));
+            }
+        }
+    }
+    axes
+}
+
+// flip the last vector for a right-handed cell
+fn right_handed(m: [[isize; 3]; 3]) -> [[isize; 3]; 3] {
+    if imatrix_det(m) < 0 {
+        [m[0], m[1], m[2].map(|x| -x)]
+    } else {
+        m
+    }
+}
+
+// Apply rational centering matrix `q` to conventional cell `conv`, or None
+// if the result is not an integer matrix.
+fn centered(q: [[f64; 3]; 3], conv: [[isize; 3]; 3]) -> Option<[[isize; 3]; 3]> {
+    let m = Matrix3f::from_row_slice(&q.concat()) * imatrix_to_matrix3f(conv);
+    let p = matrix3f_to_imatrix(m);
+    if (imatrix_to_matrix3f(p) - m).amax() < 1e-8 {
+        Some(p)
+    } else {
+        None
+    }
+}
+
+const FACE_CENTERED: [[f64; 3]; 3] = [[0.0, 0.5, 0.5], [0.5, 0.0, 0.5], [0.5, 0.5, 0.0]];
+const BODY_CENTERED: [[f64; 3]; 3] = [[-0.5, 0.5, 0.5], [0.5, -0.5, 0.5], [0.5, 0.5, -0.5]];
+const BASE_CENTERED: [[f64; 3]; 3] = [[0.5, -0.5, 0.0], [0.5, 0.5, 0.0], [0.0, 0.0, 1.0]];
+// centering on the face of the unique axis `a` and `b`, as in Setyawan and
+// Curtarolo
+const MONOCLINIC_BASE_CENTERED: [[f64; 3]; 3] =
+    [[0.5, 0.5, 0.0], [-0.5, 0.5, 0.0], [0.0, 0.0, 1.0]];
+const RHOMBOHEDRAL: [[f64; 3]; 3] = [
+    [2.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0],
+    [-1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0],
+    [-1.0 / 3.0, -2.0 / 3.0, 1.0 / 3.0],
+];
+
+// Tolerances for the boundaries between variations of a Bravais lattice,
+// such as kγ = 90° for TRI2a and MCLC2: angles in degrees, and relative
+// differences for conditions on lengths.
+const ANGLE_TOLERANCE: f64 = 1e-3;
+const LENGTH_TOLERANCE: f64 = 1e-5;
+
+impl Lattice {
+    // Find the standard primitive cell. `self` is assumed to be Niggli
+    // reduced, so that short lattice vectors have small indices.
+    fn standard_cell_reduced(&self, symprec: f64) -> Result<StandardCell> {
+        use BravaisLattice::*;
+
+        let ops = self.point_group_operations(symprec);
+        let cart = |u: [isize; 3]| self.direction_to_cart(u);
+        let norm = |u: [isize; 3]| cart(u).norm();
+        let angle = |u: [isize; 3], v: [isize; 3]| cart(u).angle(&cart(v)).to_degrees();
+        let integral = |q: [[f64; 3]; 3], conv| centered(q, conv).is_some();
+
+        let cell = match ops.len() {
+            48 => {
+                let axes = rotation_axes(&ops, 4);
+                ensure!(axes.len() == 3, "invalid cubic operations");
+                let conv = right_handed([axes[0].0, axes[1].0, axes[2].0]);
+                let (bravais, q) = match imatrix_det(conv) {
+                    1 => (Cub, [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]),
+                    2 => (Bcc, BODY_CENTERED),
+                    4 => (Fcc, FACE_CENTERED),
+                    n => bail!("invalid cubic cell of multiplicity {n}"),
+                };
+                let a = norm(conv[0]);
+                StandardCell {
+                    bravais,
+                    prim: centered(q, conv).unwrap(),
+                    params: [a, a, a, 90.0],
+                }
+            }
+            16 => {
+                let (c, w4) = rotation_axes(&ops, 4)[0];
+                // the smallest cell spanned by 2-fold axes normal to c
+                let conv = rotation_axes(&ops, 2)
+                    .into_iter()
+                    .filter(|&(u, _)| u != c)
+                    .map(|(u, _)| right_handed([u, imatrix_mul_vec(w4, u), c]))
+                    .min_by_key(|&m| imatrix_det(m))
+                    .unwrap();
+                let (a, c) = (norm(conv[0]), norm(conv[2]));
+                let (bravais, prim) = match imatrix_det(conv) {
+                    1 => (Tet, conv),
+                    2 if c < a => (Bct1, centered(BODY_CENTERED, conv).unwrap()),
+                    2 => (Bct2, centered(BODY_CENTERED, conv).unwrap()),
+                    n => bail!("invalid tetragonal cell of multiplicity {n}"),
+                };
+                StandardCell {
+                    bravais,
+                    prim,
+                    params: [a, a, c, 90.0],
+                }
+            }
+            8 => {
+                let mut axes: Vec<_> = rotation_axes(&ops, 2).into_iter().map(|(u, _)| u).collect();
+                ensure!(axes.len() == 3, "invalid orthorhombic operations");
+                axes.sort_by(|&u, &v| norm(u).total_cmp(&norm(v)));
+                let mut conv = right_handed([axes[0], axes[1], axes[2]]);
+                let (bravais, prim) = match imatrix_det(conv) {
+                    1 => (Orc, conv),
+                    4 => {
+                        let [a, b, c] = conv.map(norm);
+                        let x = 1.0 / (a * a) - 1.0 / (b * b) - 1.0 / (c * c);
+                        let bravais = if (x * a * a).abs() < LENGTH_TOLERANCE {
+                            Orcf3
+                        } else if x > 0.0 {
+                            Orcf1
+                        } else {
+                            Orcf2
+                        };
+                        (bravais, centered(FACE_CENTERED, conv).unwrap())
+                    }
+                    2 if integral(BODY_CENTERED, conv) => {
+                        (Orci, centered(BODY_CENTERED, conv).unwrap())
+                    }
+                    2 => {
+                        // move the centered face to ab, keeping a < b
+                        let k = (0..3)
+                            .find(|&k| {
+                                integral(
+                                    BASE_CENTERED,
+                                    [conv[(k + 1) % 3], conv[(k + 2) % 3], conv[k]],
+                                )
+                            })
+                            .ok_or(format_err!("invalid base-centered orthorhombic cell"))?;
+                        let (i, j) = ((k + 1) % 3, (k + 2) % 3);
+                        let (i, j) = if norm(conv[i]) < norm(conv[j]) {
+                            (i, j)
+                        } else {
+                            (j, i)
+                        };
+                        conv = right_handed([conv[i], conv[j], conv[k]]);
+                        (Orcc, centered(BASE_CENTERED, conv).unwrap())
+                    }
+                    n => bail!("invalid orthorhombic cell of multiplicity {n}"),
+                };
+                let [a, b, c] = conv.map(norm);
+                StandardCell {
+                    bravais,
+                    prim,
+                    params: [a, b, c, 90.0],
+                }
+            }
+            24 | 12 => {
+                let order = if ops.len() == 24 { 6 } else { 3 };
+                let (c, w) = rotation_axes(&ops, order)[0];
+                // rotation by 120 degrees
+                let w3 = if order == 6 { imatrix_mul(w, w) } else { w };
+                let a = rotation_axes(&ops, 2)
+                    .into_iter()
+                    .map(|(u, _)| u)
+                    .filter(|&u| u != c)
+                    .min_by(|&u, &v| norm(u).total_cmp(&norm(v)))
+                    .unwrap();
+                let mut b = imatrix_mul_vec(w3, a);
+                if imatrix_det([a, b, c]) < 0 {
+                    b = imatrix_mul_vec(w3, b);
+                }
+                let conv = [a, b, c];
+                if order == 6 {
+                    ensure!(imatrix_det(conv) == 1, "invalid hexagonal cell");
+                    StandardCell {
+                        bravais: Hex,
+                        prim: conv,
+                        params: [norm(a), norm(a), norm(c), 90.0],
+                    }
+                } else {
+                    ensure!(imatrix_det(conv) == 3, "invalid rhombohedral cell");
+                    // switch from reverse to obverse setting
+                    let prim = centered(RHOMBOHEDRAL, conv)
+                        .or_else(|| centered(RHOMBOHEDRAL, [a.map(|x| -x), b.map(|x| -x), c]))
+                        .ok_or(format_err!("invalid rhombohedral cell"))?;
+                    let alpha = angle(prim[0], prim[1]);
+                    let bravais = if alpha < 90.0 { Rhl1 } else { Rhl2 };
+                    let a = norm(prim[0]);
+                    StandardCell {
+                        bravais,
+                        prim,
+                        params: [a, a, a, alpha],
+                    }
+                }
+            }
+            4 => {
+                let (a, w2) = rotation_axes(&ops, 2)[0];
+                // lattice vectors normal to the unique axis: w x = -x
+                let mut plane: Vec<_> = std::iter::repeat_n(-2..=2, 3)
+                    .multi_cartesian_product()
+                    .map(|x| [x[0], x[1], x[2]])
+                    .filter(|&x| x != [0; 3] && imatrix_mul_vec(w2, x) == x.map(|i| -i))
+                    .collect();
+                plane.sort_by(|&u, &v| norm(u).total_cmp(&norm(v)));
+                let b = plane[0];
+                let c = plane
+                    .iter()
+                    .copied()
+                    .find(|&v| cart(b).cross(&cart(v)).norm() > 1e-6 * norm(b) * norm(v))
+                    .unwrap();
+                let c = if cart(b).dot(&cart(c)) < 0.0 {
+                    c.map(|x| -x)
+                } else {
+                    c
+                };
+                let a = if imatrix_det([a, b, c]) < 0 {
+                    a.map(|x| -x)
+                } else {
+                    a
+                };
+                let conv = [a, b, c];
+                match imatrix_det(conv) {
+                    1 => StandardCell {
+                        bravais: Mcl,
+                        prim: conv,
+                        params: [norm(a), norm(b), norm(c), angle(b, c)],
+                    },
+                    2 => {
+                        // b spans the centered face with the unique axis,
+                        // and c completes a basis of the plane
+                        let b = plane
+                            .iter()
+                            .copied()
+                            .find(|&b| (0..3).all(|i| (a[i] + b[i]) % 2 == 0))
+                            .ok_or(format_err!("invalid C-centered monoclinic cell"))?;
+                        let c = plane
+                            .iter()
+                            .copied()
+                            .find(|&c| imatrix_det([a, b, c]).abs() == 2)
+                            .unwrap();
+                        let c = if cart(b).dot(&cart(c)) < 0.0 {
+                            c.map(|x| -x)
+                        } else {
+                            c
+                        };
+                        let a = if imatrix_det([a, b, c]) < 0 {
+                            a.map(|x| -x)
+                        } else {
+                            a
+                        };
+                        let conv = [a, b, c];
+                        let prim = centered(MONOCLINIC_BASE_CENTERED, conv).unwrap();
+                        let [a, b, c] = conv.map(norm);
+                        let alpha = angle(conv[1], conv[2]);
+                        // the angle between the first two reciprocal vectors
+                        let [p1, p2, p3] = prim.map(cart);
+                        let kgamma = p2.cross(&p3).angle(&p3.cross(&p1)).to_degrees();
+                        let (sin, cos) = alpha.to_radians().sin_cos();
+                        let x = b * cos / c + (b * sin / a).powi(2);
+                        let bravais = if (kgamma - 90.0).abs() < ANGLE_TOLERANCE {
+                            Mclc2
+                        } else if kgamma > 90.0 {
+                            Mclc1
+                        } else if (x - 1.0).abs() < LENGTH_TOLERANCE {
+                            Mclc4
+                        } else if x < 1.0 {
+                            Mclc3
+                        } else {
+                            Mclc5
+                        };
+                        StandardCell {
+                            bravais,
+                            prim,
+                            params: [a, b, c, alpha],
+                        }
+                    }
+                    n => bail!("invalid monoclinic cell of multiplicity {n}"),
+                }
+            }
+            2 => {
+                // reduced reciprocal cell with all angles acute or all obtuse
+                let (rec, t) = self.reciprocal().niggli_reduce();
+                let t = if imatrix_det(t) < 0 {
+                    t.map(|r| r.map(|x| -x))
+                } else {
+                    t
+                };
+                let angles = rec.angles();
+                // a reduced cell with a right angle has the other two angles
+                // obtuse, so TRI2b never occurs
+                let obtuse = angles.iter().any(|&x| x > 90.0 - ANGLE_TOLERANCE);
+                // the last angle is made the smallest (obtuse) or largest (acute)
+                let k = if obtuse { angles.imin() } else { angles.imax() };
+                let t = [t[(k + 1) % 3], t[(k + 2) % 3], t[k]];
+                let bravais = if !obtuse {
+                    Tri1b
+                } else if (angles[k] - 90.0).abs() < ANGLE_TOLERANCE {
+                    Tri2a
+                } else {
+                    Tri1a
+                };
+                // the real space basis dual to the reciprocal one
+                let prim = imatrix_transpose(imatrix_inv_unimodular(t));
+                let [a, b, c] = prim.map(norm);
+                StandardCell {
+                    bravais,
+                    prim,
+                    params: [a, b, c, angle(prim[1], prim[2])],
+                }
+            }
+            n => bail!("unexpected number of point group operations: {n}"),
+        };
+        ensure!(
+            imatrix_det(cell.prim) == 1,
+            "invalid standard primitive cell"
+        );
+        Ok(cell)
+    }
+
+    // The standard primitive cell with `prim` in the basis of `self`.
+    fn standard_cell(&self, symprec: f64) -> Result<StandardCell> {
+        let (reduced, m) = self.niggli_reduce();
+        let mut cell = reduced.standard_cell_reduced(symprec)?;
+        cell.prim = imatrix_mul(cell.prim, m);
+        Ok(cell)
+    }
+
+    /// Return the Bravais lattice type following the convention of Setyawan
+    /// and Curtarolo. `symprec` is the tolerance for lattice symmetry as in
+    /// [`Lattice::point_group_operations`]. The boundaries between
+    /// variations, such as kγ = 90° for TRI2a and MCLC2, are detected within
+    /// fixed tolerances of 1e-3 degrees for angles and 1e-5 for relative
+    /// lengths.
+    pub fn bravais_lattice(&self, symprec: f64) -> Result<BravaisLattice> {
+        Ok(self.standard_cell(symprec)?.bravais)
+    }
+}
+// b71f4a90 ends here
+
+// [[file:../lattice.note::09e6c3ad][09e6c3ad]]
+type LabelledPoints = Vec<(&'static str, [f64; 3])>;
+
+// High-symmetry points in fractional coordinates of the reciprocal basis of
+// the standard primitive cell, and the path as labels separated by `-`,
+// with `|` for breaks.
+fn setyawan_curtarolo(bravais: BravaisLattice, params: [f64; 4]) -> (LabelledPoints, &'static str) {
+    use BravaisLattice::*;
+
+    let [a, b, c, alpha] = params;
+    let alpha = alpha.to_radians();
+    let (points, path) = match bravais {
+        Cub => (
+            vec![
+                ("M", [0.5, 0.5, 0.0]),
+                ("R", [0.5, 0.5, 0.5]),
+                ("X", [0.0, 0.5, 0.0]),
+            ],
+            "Γ-X-M-Γ-R-X|M-R",
+        ),
+        Fcc => (
+            vec![
+                ("K", [3.0 / 8.0, 3.0 / 8.0, 3.0 / 4.0]),
+                ("L", [0.5, 0.5, 0.5]),
+                ("U", [5.0 / 8.0, 1.0 / 4.0, 5.0 / 8.0]),
+                ("W", [0.5, 0.25, 0.75]),
+                ("X", [0.5, 0.0, 0.5]),
+            ],
+            "Γ-X-W-K-Γ-L-U-W-L-K|U-X",
+        ),
+        Bcc => (
+            vec![
+                ("H", [0.5, -0.5, 0.5]),
+                ("P", [0.25, 0.25, 0.25]),
+                ("N", [0.0, 0.0, 0.5]),
+            ],
+            "Γ-H-N-Γ-P-H|P-N",
+        ),
+        Tet => (
+            vec![
+                ("A", [0.5, 0.5, 0.5]),
+                ("M", [0.5, 0.5, 0.0]),
+                ("R", [0.0, 0.5, 0.5]),
+                ("X", [0.0, 0.5, 0.0]),
+                ("Z", [0.0, 0.0, 0.5]),
+            ],
+            "Γ-X-M-Γ-Z-R-A-Z|X-R|M-A",
+        ),
+        Bct1 => {
+            let eta = (1.0 + c * c / (a * a)) / 4.0;
+            (
+                vec![
+                    ("M", [-0.5, 0.5, 0.5]),
+                    ("N", [0.0, 0.5, 0.0]),
+                    ("P", [0.25, 0.25, 0.25]),
+                    ("X", [0.0, 0.0, 0.5]),
+                    ("Z", [eta, eta, -eta]),
+                    ("Z1", [-eta, 1.0 - eta, eta]),
+                ],
+                "Γ-X-M-Γ-Z-P-N-Z1-M|X-P",
+            )
+        }
+        Bct2 => {
+            let eta = (1.0 + a * a / (c * c)) / 4.0;
+            let zeta = a * a / (2.0 * c * c);
+            (
+                vec![
+                    ("N", [0.0, 0.5, 0.0]),
+                    ("P", [0.25, 0.25, 0.25]),
+                    ("Σ", [-eta, eta, eta]),
+                    ("Σ1", [eta, 1.0 - eta, -eta]),
+                    ("X", [0.0, 0.0, 0.5]),
+                    ("Y", [-zeta, zeta, 0.5]),
+                    ("Y1", [0.5, 0.5, -zeta]),
+                    ("Z", [0.5, 0.5, -0.5]),
+                ],
+                "Γ-X-Y-Σ-Γ-Z-Σ1-N-P-Y1-Z|X-P",
+            )
+        }
+        Orc => (
+            vec![
+                ("R", [0.5, 0.5, 0.5]),
+                ("S", [0.5, 0.5, 0.0]),
+                ("T", [0.0, 0.5, 0.5]),
+                ("U", [0.5, 0.0, 0.5]),
+                ("X", [0.5, 0.0, 0.0]),
+                ("Y", [0.0, 0.5, 0.0]),
+                ("Z", [0.0, 0.0, 0.5]),
+            ],
+            "Γ-X-S-Y-Γ-Z-U-R-T-Z|Y-T|U-X|S-R",
+        ),
+        Orcf1 | Orcf3 => {
+            let zeta = (1.0 + a * a / (b * b) - a * a / (c * c)) / 4.0;
+            let eta = (1.0 + a * a / (b * b) + a * a / (c * c)) / 4.0;
+            let points = vec![
+                ("A", [0.5, 0.5 + zeta, zeta]),
+                ("A1", [0.5, 0.5 - zeta, 1.0 - zeta]),
+                ("L", [0.5, 0.5, 0.5]),
+                ("T", [1.0, 0.5, 0.5]),
+                ("X", [0.0, eta, eta]),
+                ("X1", [1.0, 1.0 - eta, 1.0 - eta]),
+                ("Y", [0.5, 0.0, 0.5]),
+                ("Z", [0.5, 0.5, 0.0]),
+            ];
+            if bravais == Orcf1 {
+                (points, "Γ-Y-T-Z-Γ-X-A1-Y|T-X1|X-A-Z|L-Γ")
+            } else {
+                (points, "Γ-Y-T-Z-Γ-X-A1-Y|X-A-Z|L-Γ")
+            }
+        }
+        Orcf2 => {
+            let eta = (1.0 + a * a / (b * b) - a * a / (c * c)) / 4.0;
+            let phi = (1.0 + c * c / (b * b) - c * c / (a * a)) / 4.0;
+            let delta = (1.0 + b * b / (a * a) - b * b / (c * c)) / 4.0;
+            (
+                vec![
+                    ("C", [0.5, 0.5 - eta, 1.0 - eta]),
+                    ("C1", [0.5, 0.5 + eta, eta]),
+                    ("D", [0.5 - delta, 0.5, 1.0 - delta]),
+                    ("D1", [0.5 + delta, 0.5, delta]),
+                    ("L", [0.5, 0.5, 0.5]),
+                    ("H", [1.0 - phi, 0.5 - phi, 0.5]),
+                    ("H1", [phi, 0.5 + phi, 0.5]),
+                    ("X", [0.0, 0.5, 0.5]),
+                    ("Y", [0.5, 0.0, 0.5]),
+                    ("Z", [0.5, 0.5, 0.0]),
+                ],
+                "Γ-Y-C-D-X-Γ-Z-D1-H-C|C1-Z|X-H1|H-Y|L-Γ",
+            )
+        }
+        Orci => {
+            let zeta = (1.0 + a * a / (c * c)) / 4.0;
+            let eta = (1.0 + b * b / (c * c)) / 4.0;
+            let delta = (b * b - a * a) / (4.0 * c * c);
+            let mu = (a * a + b * b) / (4.0 * c * c);
+            (
+                vec![
+                    ("L", [-mu, mu, 0.5 - delta]),
+                    ("L1", [mu, -mu, 0.5 + delta]),
+                    ("L2", [0.5 - delta, 0.5 + delta, -mu]),
+                    ("R", [0.0, 0.5, 0.0]),
+                    ("S", [0.5, 0.0, 0.0]),
+                    ("T", [0.0, 0.0, 0.5]),
+                    ("W", [0.25, 0.25, 0.25]),
+                    ("X", [-zeta, zeta, zeta]),
+                    ("X1", [zeta, 1.0 - zeta, -zeta]),
+                    ("Y", [eta, -eta, eta]),
+                    ("Y1", [1.0 - eta, eta, -eta]),
+                    ("Z", [0.5, 0.5, -0.5]),
+                ],
+                "Γ-X-L-T-W-R-X1-Z-Γ-Y-S-W|L1-Y|Y1-Z",
+            )
+        }
+        Orcc => {
+            let zeta = (1.0 + a * a / (b * b)) / 4.0;
+            (
+                vec![
+                    ("A", [zeta, zeta, 0.5]),
+                    ("A1", [-zeta, 1.0 - zeta, 0.5]),
+                    ("R", [0.0, 0.5, 0.5]),
+                    ("S", [0.0, 0.5, 0.0]),
+                    ("T", [-0.5, 0.5, 0.5]),
+                    ("X", [zeta, zeta, 0.0]),
+                    ("X1", [-zeta, 1.0 - zeta, 0.0]),
+                    ("Y", [-0.5, 0.5, 0.0]),
+                    ("Z", [0.0, 0.0, 0.5]),
+                ],
+                "Γ-X-S-R-A-Z-Γ-Y-X1-A1-T-Y|Z-T",
+            )
+        }
+        Hex => (
+            vec![
+                ("A", [0.0, 0.0, 0.5]),
+                ("H", [1.0 / 3.0, 1.0 / 3.0, 0.5]),
+                ("K", [1.0 / 3.0, 1.0 / 3.0, 0.0]),
+                ("L", [0.5, 0.0, 0.5]),
+                ("M", [0.5, 0.0, 0.0]),
+            ],
+            "Γ-M-K-Γ-A-L-H-A|L-M|K-H",
+        ),
+        Rhl1 => {
+            let eta = (1.0 + 4.0 * alpha.cos()) / (2.0 + 4.0 * alpha.cos());
+            let nu = 0.75 - eta / 2.0;
+            (
+                vec![
+                    ("B", [eta, 0.5, 1.0 - eta]),
+                    ("B1", [0.5, 1.0 - eta, eta - 1.0]),
+                    ("F", [0.5, 0.5, 0.0]),
+                    ("L", [0.5, 0.0, 0.0]),
+                    ("L1", [0.0, 0.0, -0.5]),
+                    ("P", [eta, nu, nu]),
+                    ("P1", [1.0 - nu, 1.0 - nu, 1.0 - eta]),
+                    ("P2", [nu, nu, eta - 1.0]),
+                    ("Q", [1.0 - nu, nu, 0.0]),
+                    ("X", [nu, 0.0, -nu]),
+                    ("Z", [0.5, 0.5, 0.5]),
+                ],
+                "Γ-L-B1|B-Z-Γ-X|Q-F-P1-Z|L-P",
+            )
+        }
+        Rhl2 => {
+            let eta = 1.0 / (2.0 * (alpha / 2.0).tan().powi(2));
+            let nu = 0.75 - eta / 2.0;
+            (
+                vec![
+                    ("F", [0.5, -0.5, 0.0]),
+                    ("L", [0.5, 0.0, 0.0]),
+                    ("P", [1.0 - nu, -nu, 1.0 - nu]),
+                    ("P1", [nu, nu - 1.0, nu - 1.0]),
+                    ("Q", [eta, eta, eta]),
+                    ("Q1", [1.0 - eta, -eta, -eta]),
+                    ("Z", [0.5, -0.5, 0.5]),
+                ],
+                "Γ-P-Z-Q-Γ-F-P1-Q1-L-Z",
+            )
+        }
+        Mcl => {
+            let eta = (1.0 - b * alpha.cos() / c) / (2.0 * alpha.sin().powi(2));
+            let nu = 0.5 - eta * c * alpha.cos() / b;
+            (
+                vec![
+                    ("A", [0.5, 0.5, 0.0]),
+                    ("C", [0.0, 0.5, 0.5]),
+                    ("D", [0.5, 0.0, 0.5]),
+                    ("D1", [0.5, 0.0, -0.5]),
+                    ("E", [0.5, 0.5, 0.5]),
+                    ("H", [0.0, eta, 1.0 - nu]),
+                    ("H1", [0.0, 1.0 - eta, nu]),
+                    ("H2", [0.0, eta, -nu]),
+                    ("M", [0.5, eta, 1.0 - nu]),
+                    ("M1", [0.5, 1.0 - eta, nu]),
+                    ("M2", [0.5, eta, -nu]),
+                    ("X", [0.0, 0.5, 0.0]),
+                    ("Y", [0.0, 0.0, 0.5]),
+                    ("Y1", [0.0, 0.0, -0.5]),
+                    ("Z", [0.5, 0.0, 0.0]),
+                ],
+                "Γ-Y-H-C-E-M1-A-X-H1|M-D-Z|Y-D",
+            )
+        }
+        Mclc1 | Mclc2 => {
+            let zeta = (2.0 - b * alpha.cos() / c) / (4.0 * alpha.sin().powi(2));
+            let eta = 0.5 + 2.0 * zeta * c * alpha.cos() / b;
+            let psi = 0.75 - a * a / (4.0 * b * b * alpha.sin().powi(2));
+            let phi = psi + (0.75 - psi) * b * alpha.cos() / c;
+            let mut points = vec![
+                ("F", [1.0 - zeta, 1.0 - zeta, 1.0 - eta]),
+                ("F1", [zeta, zeta, eta]),
+                ("F2", [-zeta, -zeta, 1.0 - eta]),
+                ("I", [phi, 1.0 - phi, 0.5]),
+                ("I1", [1.0 - phi, phi - 1.0, 0.5]),
+                ("L", [0.5, 0.5, 0.5]),
+                ("M", [0.5, 0.0, 0.5]),
+                ("N", [0.5, 0.0, 0.0]),
+                ("N1", [0.0, -0.5, 0.0]),
+                ("X", [1.0 - psi, psi - 1.0, 0.0]),
+                ("Y", [0.5, 0.5, 0.0]),
+                ("Y1", [-0.5, -0.5, 0.0]),
+                ("Z", [0.0, 0.0, 0.5]),
+            ];
+            if bravais == Mclc1 {
+                points.push(("X1", [psi, 1.0 - psi, 0.0]));
+                points.push(("X2", [psi - 1.0, -psi, 0.0]));
+                (points, "Γ-Y-F-L-I|I1-Z-F1|Y-X1|X-Γ-N|M-Γ")
+            } else {
+                points.push(("F3", [1.0 - zeta, -zeta, 1.0 - eta]));
+                (points, "Γ-Y-F-L-I|I1-Z-F1|N-Γ-M")
+            }
+        }
+        Mclc3 | Mclc4 => {
+            let mu = (1.0 + b * b / (a * a)) / 4.0;
+            let delta = b * c * alpha.cos() / (2.0 * a * a);
+            let zeta = mu - 0.25 + (1.0 - b * alpha.cos() / c) / (4.0 * alpha.sin().powi(2));
+            let eta = 0.5 + 2.0 * zeta * c * alpha.cos() / b;
+            let phi = 1.0 + zeta - 2.0 * mu;
+            let psi = eta - 2.0 * delta;
+            let points = vec![
+                ("F", [1.0 - phi, 1.0 - phi, 1.0 - psi]),
+                ("F1", [phi, phi - 1.0, psi]),
+                ("F2", [1.0 - phi, -phi, 1.0 - psi]),
+                ("H", [zeta, zeta, eta]),
+                ("H1", [1.0 - zeta, -zeta, 1.0 - eta]),
+                ("H2", [-zeta, -zeta, 1.0 - eta]),
+                ("I", [0.5, -0.5, 0.5]),
+                ("M", [0.5, 0.0, 0.5]),
+                ("N", [0.5, 0.0, 0.0]),
+                ("N1", [0.0, -0.5, 0.0]),
+                ("X", [0.5, -0.5, 0.0]),
+                ("Y", [mu, mu, delta]),
+                ("Y1", [1.0 - mu, -mu, -delta]),
+                ("Y2", [-mu, -mu, -delta]),
+                ("Y3", [mu, mu - 1.0, delta]),
+                ("Z", [0.0, 0.0, 0.5]),
+            ];
+            if bravais == Mclc3 {
+                (points, "Γ-Y-F-H-Z-I-F1|H1-Y1-X-Γ-N|M-Γ")
+            } else {
+                (points, "Γ-Y-F-H-Z-I|H1-Y1-X-Γ-N|M-Γ")
+            }
+        }
+        Mclc5 => {
+            let zeta = (b * b / (a * a) + (1.0 - b * alpha.cos() / c) / alpha.sin().powi(2)) / 4.0;
+            let eta = 0.5 + 2.0 * zeta * c * alpha.cos() / b;
+            let mu = eta / 2.0 + b * b / (4.0 * a * a) - b * c * alpha.cos() / (2.0 * a * a);
+            let nu = 2.0 * mu - zeta;
+            let rho = 1.0 - zeta * a * a / (b * b);
+            let omega = (4.0 * nu - 1.0 - b * b * alpha.sin().powi(2) / (a * a)) * c
+                / (2.0 * b * alpha.cos());
+            let delta = zeta * c * alpha.cos() / b + omega / 2.0 - 0.25;
+            (
+                vec![
+                    ("F", [nu, nu, omega]),
+                    ("F1", [1.0 - nu, 1.0 - nu, 1.0 - omega]),
+                    ("F2", [nu, nu - 1.0, omega]),
+                    ("H", [zeta, zeta, eta]),
+                    ("H1", [1.0 - zeta, -zeta, 1.0 - eta]),
+                    ("H2", [-zeta, -zeta, 1.0 - eta]),
+                    ("I", [rho, 1.0 - rho, 0.5]),
+                    ("I1", [1.0 - rho, rho - 1.0, 0.5]),
+                    ("L", [0.5, 0.5, 0.5]),
+                    ("M", [0.5, 0.0, 0.5]),
+                    ("N", [0.5, 0.0, 0.0]),
+                    ("N1", [0.0, -0.5, 0.0]),
+                    ("X", [0.5, -0.5, 0.0]),
+                    ("Y", [mu, mu, delta]),
+                    ("Y1", [1.0 - mu, -mu, -delta]),
+                    ("Y2", [-mu, -mu, -delta]),
+                    ("Y3", [mu, mu - 1.0, delta]),
+                    ("Z", [0.0, 0.0, 0.5]),
+                ],
+                "Γ-Y-F-L-I|I1-Z-H-F1|H1-Y1-X-Γ-N|M-Γ",
+            )
+        }
+        Tri1a | Tri2a => (
+            vec![
+                ("L", [0.5, 0.5, 0.0]),
+                ("M", [0.0, 0.5, 0.5]),
+                ("N", [0.5, 0.0, 0.5]),
+                ("R", [0.5, 0.5, 0.5]),
+                ("X", [0.5, 0.0, 0.0]),
+                ("Y", [0.0, 0.5, 0.0]),
+                ("Z", [0.0, 0.0, 0.5]),
+            ],
+            "X-Γ-Y|L-Γ-Z|N-Γ-M|R-Γ",
+        ),
+        Tri1b => (
+            vec![
+                ("L", [0.5, -0.5, 0.0]),
+                ("M", [0.0, 0.0, 0.5]),
+                ("N", [-0.5, -0.5, 0.5]),
+                ("R", [0.0, -0.5, 0.5]),
+                ("X", [0.0, -0.5, 0.0]),
+                ("Y", [0.5, 0.0, 0.0]),
+                ("Z", [-0.5, 0.0, 0.5]),
+            ],
+            "X-Γ-Y|L-Γ-Z|N-Γ-M|R-Γ",
+        ),
+    };
+    let points = std::iter::once(("Γ", [0.0; 3])).chain(points).collect();
+    (points, path)
+}
+
+/// A point sampled along a k-path.
+#[derive(Debug, Clone)]
+pub struct KPoint {
+    /// Fractional coordinates in the reciprocal basis of the lattice.
+    pub coords: Vector3f,
+    /// Accumulated distance along the path in 1/Angstrom (including the
+    /// factor of 2π).
+    pub distance: f64,
+    /// The label of high-symmetry point, if any.
+    pub label: Option<String>,
+}
+
+/// High-symmetry points and the path connecting them for band structure
+/// calculations.
+#[derive(Debug, Clone)]
+pub struct KPath {
+    bravais: BravaisLattice,
+    reciprocal: Lattice,
+    points: Vec<(String, Vector3f)>,
+    segments: Vec<Vec<String>>,
+}
+
+impl KPath {
+    /// The Bravais lattice type.
+    pub fn bravais(&self) -> BravaisLattice {
+        self.bravais
+    }
+
+    /// Labelled high-symmetry points in fractional coordinates of the
+    /// reciprocal basis of the lattice.
+    pub fn points(&self) -> &[(String, Vector3f)] {
+        &self.points
+    }
+
+    /// Return the fractional coordinates of high-symmetry point `label`.
+    pub fn point(&self, label: &str) -> Option<Vector3f> {
+        self.points
+            .iter()
+            .find(|(l, _)| l == label)
+            .map(|(_, k)| *k)
+    }
+
+    /// Continuous segments of the path as lists of labels.
+    pub fn segments(&self) -> &[Vec<String>] {
+        &self.segments
+    }
+
+    /// Sample the path with `density` points per 1/Angstrom (including the
+    /// factor of 2π). Each line between two high-symmetry points has at
+    /// least one interval, and the distance does not increase across breaks
+    /// between segments.
+    pub fn interpolate(&self, density: f64) -> Vec<KPoint> {
+        assert!(density > 0.0, "invalid density: {density}");
+
+        let mut kpoints = vec![];
+        let mut x = 0.0;
+        for segment in &self.segments {
+            for (i, (l1, l2)) in segment.iter().tuple_windows().enumerate() {
+                let k1 = self.point(l1).unwrap();
+                let k2 = self.point(l2).unwrap();
+                let length = (self.reciprocal.matrix() * (k2 - k1)).norm();
+                let n = ((length * density).ceil() as usize).max(1);
+                for j in (if i == 0 { 0 } else { 1 })..=n {
+                    let t = j as f64 / n as f64;
+                    let label = match j {
+                        0 => Some(l1.clone()),
+                        j if j == n => Some(l2.clone()),
+                        _ => None,
+                    };
+                    kpoints.push(KPoint {
+                        coords: k1 + (k2 - k1) * t,
+                        distance: x + length * t,
+                        label,
+                    });
+                }
+                x += length;
+            }
+        }
+        kpoints
+    }
+}
+
+impl Lattice {
+    /// Return the high-symmetry k-path for band structures following
+    /// Setyawan and Curtarolo. The points are given in fractional
+    /// coordinates of the reciprocal basis of `self`, which need not be the
+    /// standard primitive cell. `symprec` is the tolerance for lattice
+    /// symmetry.
+    ///
+    /// Reference
+    /// ---------
+    /// - Setyawan, W.; Curtarolo, S. Comput. Mater. Sci. 2010, 49, 299–312.
+    pub fn kpath(&self, symprec: f64) -> Result<KPath> {
+        let cell = self.standard_cell(symprec)?;
+        let (points, path) = setyawan_curtarolo(cell.bravais, cell.params);
+        // k_self = P^-1 k_std, with P the standard primitive cell
+        let p_inv = imatrix_to_matrix3f(imatrix_inv_unimodular(cell.prim));
+        let points = points
+            .into_iter()
+            .map(|(l, k)| (l.to_string(), p_inv * Vector3f::from(k)))
+            .collect();
+        let segments = path
+            .split('|')
+            .map(|s| s.split('-').map(|l| l.to_string()).collect())
+            .collect();
+        Ok(KPath {
+            bravais: cell.bravais,
+            reciprocal: self.reciprocal_2pi(),
+            points,
+            segments,
+        })
+    }
+}
+// 09e6c3ad ends here
+
+// [[file:../lattice.note::f4c7a3e2][f4c7a3e2]]
+#[test]
+fn test_kpath() {
+    use vecfx::approx::*;
+    use BravaisLattice::*;
+
+    // C-centered monoclinic in the standard primitive cell
+    let mclc = |a: f64, b: f64, c: f64, alpha: f64| {
+        let (sin, cos) = alpha.to_radians().sin_cos();
+        Lattice::new([
+            [a / 2.0, b / 2.0, 0.0],
+            [-a / 2.0, b / 2.0, 0.0],
+            [0.0, c * cos, c * sin],
+        ])
+    };
+    // triclinic with given reciprocal cell
+    let tri =
+        |alpha, beta, gamma| Lattice::from_params(0.3, 0.25, 0.2, alpha, beta, gamma).reciprocal();
+    let lattices = [
+        (Lattice::from_params(3.0, 3.0, 3.0, 90.0, 90.0, 90.0), Cub),
+        (
+            Lattice::new([[0.0, 2.0, 2.0], [2.0, 0.0, 2.0], [2.0, 2.0, 0.0]]),
+            Fcc,
+        ),
+        (
+            Lattice::new([[-1.5, 1.5, 1.5], [1.5, -1.5, 1.5], [1.5, 1.5, -1.5]]),
+            Bcc,
+        ),
+        (Lattice::from_params(3.0, 3.0, 5.0, 90.0, 90.0, 90.0), Tet),
+        (
+            Lattice::new([[-1.5, 1.5, 1.0], [1.5, -1.5, 1.0], [1.5, 1.5, -1.0]]),
+            Bct1,
+        ),
+        (
+            Lattice::new([[-1.5, 1.5, 2.5], [1.5, -1.5, 2.5], [1.5, 1.5, -2.5]]),
+            Bct2,
+        ),
+        (Lattice::from_params(3.0, 4.0, 5.0, 90.0, 90.0, 90.0), Orc),
+        (
+            Lattice::new([[0.0, 2.0, 2.5], [1.5, 0.0, 2.5], [1.5, 2.0, 0.0]]),
+            Orcf1,
+        ),
+        (
+            Lattice::new([[0.0, 2.5, 3.0], [2.0, 0.0, 3.0], [2.0, 2.5, 0.0]]),
+            Orcf2,
+        ),
+        (
+            Lattice::new([[-1.5, 2.0, 2.5], [1.5, -2.0, 2.5], [1.5, 2.0, -2.5]]),
+            Orci,
+        ),
+        (
+            Lattice::new([[1.5, -2.0, 0.0], [1.5, 2.0, 0.0], [0.0, 0.0, 5.0]]),
+            Orcc,
+        ),
+        (Lattice::from_params(3.0, 3.0, 5.0, 90.0, 90.0, 120.0), Hex),
+        (Lattice::from_params(4.0, 4.0, 4.0, 70.0, 70.0, 70.0), Rhl1),
+        (
+            Lattice::from_params(4.0, 4.0, 4.0, 100.0, 100.0, 100.0),
+            Rhl2,
+        ),
+        (Lattice::from_params(3.0, 4.0, 5.0, 90.0, 100.0, 90.0), Mcl),
+        (mclc(3.0, 6.0, 5.0, 60.0), Mclc1),
+        // a = b sin α
+        (mclc(3f64.sqrt() * 3.0, 6.0, 5.0, 60.0), Mclc2),
+        (mclc(6.0, 4.0, 5.0, 60.0), Mclc3),
+        // b cos α / c + b² sin² α / a² = 1
+        (mclc(20f64.sqrt(), 4.0, 5.0, 60.0), Mclc4),
+        (mclc(4.0, 4.0, 5.0, 60.0), Mclc5),
+        (
+            Lattice::from_params(3.0, 4.0, 5.0, 80.0, 100.0, 70.0),
+            Tri1a,
+        ),
+        (tri(80.0, 85.0, 75.0), Tri1b),
+        (tri(100.0, 95.0, 90.0), Tri2a),
+        // TRI2b is given in the TRI2a setting
+        (tri(80.0, 85.0, 90.0), Tri2a),
+    ];
+    for (i, (lat, bravais)) in lattices.into_iter().enumerate() {
+        // some also in a skewed basis of the same lattice
+        let skewed = lat.supercell([[1, 1, 0], [0, 1, 0], [-1, 0, 1]]);
+        let n = if i % 5 == 0 { 2 } else { 1 };
+        for lat in [lat, skewed].into_iter().take(n) {
+            let path = lat
+                .kpath(1e-5)
+                .unwrap_or_else(|e| panic!("{bravais:?}: {e}"));
+            assert_eq!(path.bravais(), bravais);
+            // all high-symmetry points are on the surface of the first
+            // Brillouin zone
+            let rec = lat.reciprocal_2pi();
+            let bz = lat.brillouin_zone();
+            for (l, k) in path.points() {
+                let k = rec.matrix() * k;
+                assert!(bz.contains(k, 1e-6), "{bravais:?} {l}");
+                if l != "Γ" {
+                    assert!(!bz.contains(k * 1.001, 1e-6), "{bravais:?} {l}");
+                }
+            }
+        }
+    }
+
+    // fcc: |ΓX| = 2π/a
+    let fcc = Lattice::new([[0.0, 2.0, 2.0], [2.0, 0.0, 2.0], [2.0, 2.0, 0.0]]);
+    let path = fcc.kpath(1e-5).unwrap();
+    let rec = fcc.reciprocal_2pi();
+    let x = rec.matrix() * path.point("X").unwrap();
+    assert_relative_eq!(x.norm(), std::f64::consts::PI / 2.0, epsilon = 1e-8);
+    let l = rec.matrix() * path.point("L").unwrap();
+    assert_relative_eq!(
+        l.norm(),
+        3f64.sqrt() * std::f64::consts::PI / 4.0,
+        epsilon = 1e-8
+    );
+    assert_eq!(path.segments().len(), 2);
+
+    let kpoints = path.interpolate(20.0);
+    assert_eq!(kpoints[0].label.as_deref(), Some("Γ"));
+    assert_eq!(kpoints.last().unwrap().label.as_deref(), Some("X"));
+    assert_eq!(kpoints.iter().filter(|k| k.label.is_some()).count(), 12);
+    assert_relative_eq!(
+        kpoints[1].distance - kpoints[0].distance,
+        1.0 / 20.0,
+        epsilon = 0.01
+    );
+    assert!(kpoints.windows(2).all(|w| w[1].distance >= w[0].distance));
+
+    // C-centered monoclinic in a nonstandard cell
+    let lat = Lattice::new([[1.5, -2.0, 0.0], [1.5, 2.0, 0.0], [1.0, 0.0, 5.0]]);
+    let path = lat.kpath(1e-5).unwrap();
+    assert!(matches!(
+        path.bravais(),
+        Mclc1 | Mclc2 | Mclc3 | Mclc4 | Mclc5
+    ));
+    assert!(path
+        .segments()
+        .iter()
+        .flatten()
+        .all(|l| path.point(l).is_some()));
+}
+// f4c7a3e2 ends here
@@ -18,16 +18,50 @@ use vecfx::*;
 // imports:1 ends here
 
 // [[file:../lattice.note::*mods][mods:1]]
+mod elastic;
+mod eos;
+mod ewald;
+mod grid;
+mod hnf;
+mod kpath;
+mod matching;
+mod mesh;
 mod mic;
+mod miller;
+mod orientation;
+mod reciprocal;
+mod reduce;
+mod strain;
 mod supercell;
+mod surface;
+mod symmetry;
 mod utils;
+mod voronoi;
+mod xrd;
 
 use crate::utils::*;
+
+pub use crate::elastic::{CrystalSystem, ElasticTensor};
+pub use crate::eos::{EosFit, EquationOfState, EV_PER_ANGSTROM3_TO_GPA};
+pub use crate::ewald::{Ewald, EwaldResult, COULOMB_CONSTANT};
+pub use crate::grid::PeriodicGrid;
+pub use crate::hnf::{hermite_normal_form, hermite_normal_forms};
+pub use crate::kpath::{BravaisLattice, KPath, KPoint};
+pub use crate::matching::LatticeMatch;
+pub use crate::miller::{
+    direction_from_uvtw, direction_to_uvtw, plane_from_hkil, plane_to_hkil, zone_axis,
+};
+pub use crate::orientation::Orientation;
+pub use crate::reciprocal::GVector;
+pub use crate::strain::{polar_decomposition, Strain, StrainMeasure};
+pub use crate::surface::Slab;
+pub use crate::voronoi::{Face, Polyhedron};
+pub use crate::xrd::{Centering, Reflection, Wavelength};
 // mods:1 ends here
 
 // [[file:../lattice.note::*base][base:1]]
 /// Periodic 3D lattice
-#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
 pub struct Lattice {
     /// internal translation matrix
     matrix: Matrix3f,
@@ -51,6 +85,9 @@ impl Default for Lattice {
 // base:1 ends here
 
 // [[file:../lattice.note::f072864d][f072864d]]
+// 1 g/cm^3 in atomic mass units per Angstrom^3
+const G_PER_CM3_TO_AMU_PER_A3: f64 = 0.602214076;
+
 impl Lattice {
     /// Construct `Lattice` from three lattice vectors.
     pub fn new<T: Into<Vector3f> + Copy>(tvs: [T; 3]) -> Self {
@@ -118,12 +155,12 @@ impl Lattice {
 
     /// Lattice length parameters: a, b, c
     pub fn lengths(&self) -> [f64; 3] {
-        get_cell_lengths(self.matrix).into()
+        get_cell_lengths(self.matrix)
     }
 
     /// Lattice angle parameters in degrees
     pub fn angles(&self) -> [f64; 3] {
-        get_cell_angles(self.matrix).into()
+        get_cell_angles(self.matrix)
     }
 
     /// Scale Lattice by a positive constant `v`
@@ -156,6 +193,44 @@ impl Lattice {
         self.inv_matrix = get_inv_matrix(&self.matrix);
     }
 
+    /// Scale Lattice isotropically to cell volume `v`, keeping its shape.
+    pub fn set_volume(&mut self, v: f64) {
+        assert!(v > 0.0, "invalid volume: {v}");
+        self.scale_by((v / self.volume().abs()).cbrt());
+    }
+
+    /// Scale Lattice to cell volume `v` by stretching the other two lattice
+    /// vectors equally, keeping lattice vector `axis` (0, 1 or 2 for a, b
+    /// or c) fixed. This is useful for scaling slabs in plane only.
+    pub fn set_volume_fixed_axis(&mut self, v: f64, axis: usize) {
+        assert!(v > 0.0, "invalid volume: {v}");
+        assert!(axis < 3, "invalid axis: {axis}");
+        let s = (v / self.volume().abs()).sqrt();
+        (0..3)
+            .filter(|&i| i != axis)
+            .for_each(|i| self.scale_by_abc(s, i));
+    }
+
+    /// Return the mass density in g/cm^3 for the cell containing `mass` in
+    /// atomic mass units.
+    pub fn density(&self, mass: f64) -> f64 {
+        mass / (self.volume().abs() * G_PER_CM3_TO_AMU_PER_A3)
+    }
+
+    /// Scale Lattice isotropically to mass density `rho` in g/cm^3 for the
+    /// cell containing `mass` in atomic mass units.
+    pub fn scale_to_density(&mut self, mass: f64, rho: f64) {
+        assert!(rho > 0.0, "invalid density: {rho}");
+        self.set_volume(mass / (rho * G_PER_CM3_TO_AMU_PER_A3));
+    }
+
+    /// Scale Lattice to mass density `rho` like `scale_to_density`, but
+    /// keep lattice vector `axis` fixed as in `set_volume_fixed_axis`.
+    pub fn scale_to_density_fixed_axis(&mut self, mass: f64, rho: f64, axis: usize) {
+        assert!(rho > 0.0, "invalid density: {rho}");
+        self.set_volume_fixed_axis(mass / (rho * G_PER_CM3_TO_AMU_PER_A3), axis);
+    }
+
     /// Get cell origin in Cartesian coordinates
     pub fn origin(&self) -> Vector3f {
         self.origin
@@ -171,6 +246,24 @@ impl Lattice {
         self.matrix * p.into() + self.origin
     }
 
+    /// Map Cartesian `positions` in this cell onto `new` cell, keeping
+    /// their fractional coordinates fixed. The origins of both cells are
+    /// taken into account.
+    pub fn map_positions_to(&self, new: &Lattice, positions: &[[f64; 3]]) -> Vec<[f64; 3]> {
+        let mut positions = positions.to_vec();
+        self.map_positions_to_mut(new, &mut positions);
+        positions
+    }
+
+    /// Map Cartesian `positions` in place like `map_positions_to`.
+    pub fn map_positions_to_mut(&self, new: &Lattice, positions: &mut [[f64; 3]]) {
+        // the affine map: p' = A' A^-1 (p - o) + o'
+        let t = new.matrix * self.inv_matrix;
+        for p in positions.iter_mut() {
+            *p = (t * (Vector3f::from(*p) - self.origin) + new.origin).into();
+        }
+    }
+
     /// Lattice vector a
     pub fn vector_a(&self) -> Vector3f {
         self.matrix.column(0).into()
@@ -208,6 +301,49 @@ impl Lattice {
         m == self.matrix
     }
 
+    // Check lattice parameters: lengths at indices in `equal` are the same
+    // within relative tolerance `tol`, and cosines of angles match those of
+    // `angles` (in degrees) within `tol`.
+    fn match_params(&self, equal: &[usize], angles: [f64; 3], tol: f64) -> bool {
+        let lengths = self.lengths();
+        let l0 = lengths[equal[0]];
+        equal.iter().all(|&i| (lengths[i] - l0).abs() <= tol * l0)
+            && self
+                .angles()
+                .iter()
+                .zip(angles)
+                .all(|(x, y)| (x.to_radians().cos() - y.to_radians().cos()).abs() <= tol)
+    }
+
+    /// Check if lattice is orthorhombic (α = β = γ = 90°) within tolerance
+    /// `tol`. Unlike `is_orthorhombic`, the cell may have any orientation.
+    ///
+    /// This and the other `is_*` predicates taking a tolerance compare
+    /// lengths relatively, and angles by their cosines.
+    pub fn is_orthorhombic_tol(&self, tol: f64) -> bool {
+        self.match_params(&[0], [90.0; 3], tol)
+    }
+
+    /// Check if lattice is tetragonal (a = b, α = β = γ = 90°) within
+    /// tolerance `tol`. Only the setting with a = b, i.e. the 4-fold axis
+    /// along c, is recognized.
+    pub fn is_tetragonal(&self, tol: f64) -> bool {
+        self.match_params(&[0, 1], [90.0; 3], tol)
+    }
+
+    /// Check if lattice is cubic (a = b = c, α = β = γ = 90°) within
+    /// tolerance `tol`.
+    pub fn is_cubic(&self, tol: f64) -> bool {
+        self.match_params(&[0, 1, 2], [90.0; 3], tol)
+    }
+
+    /// Check if lattice is hexagonal (a = b, α = β = 90°, γ = 120°) within
+    /// tolerance `tol`. Only this setting is recognized, not e.g. γ = 60°
+    /// or the 6-fold axis along a.
+    pub fn is_hexagonal(&self, tol: f64) -> bool {
+        self.match_params(&[0, 1], [90.0, 90.0, 120.0], tol)
+    }
+
     /// Wrap a point in cartesian coordinates into unit cell, obeying the
     /// periodic boundary conditions. Returns cartesian coordinates.
     pub fn wrap<T: Into<Vector3f>>(&self, vec: T) -> Vector3f {
@@ -224,6 +360,18 @@ impl Lattice {
         fcoords_wrapped.into()
     }
 
+    /// Wrap a point into the Wigner-Seitz cell around `center`, i.e. return
+    /// the periodic image of `p` closest to `center`. Returns Cartesian
+    /// coordinates.
+    ///
+    /// Compared with `wrap`, this keeps molecules or clusters compact for
+    /// visualization in skewed cells.
+    pub fn wrap_wigner_seitz<T: Into<Vector3f>>(&self, p: T, center: T) -> Vector3f {
+        let center = center.into();
+        let d = p.into() - center;
+        center + self.apply_mic(d)
+    }
+
     /// Return the shortest distance between `pi` (point i) and the periodic
     /// images of `pj` (point j) under the minimum image convention
     ///
@@ -254,3 +402,33 @@ impl Lattice {
     }
 }
 // f072864d ends here
+
+// [[file:../lattice.note::0d8e4b6a][0d8e4b6a]]
+// compare lattice vectors and origin
+impl approx::AbsDiffEq for Lattice {
+    type Epsilon = f64;
+
+    fn default_epsilon() -> f64 {
+        f64::default_epsilon()
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: f64) -> bool {
+        self.matrix.abs_diff_eq(&other.matrix, epsilon)
+            && self.origin.abs_diff_eq(&other.origin, epsilon)
+    }
+}
+
+impl approx::RelativeEq for Lattice {
+    fn default_max_relative() -> f64 {
+        f64::default_max_relative()
+    }
+
+    fn relative_eq(&self, other: &Self, epsilon: f64, max_relative: f64) -> bool {
+        self.matrix
+            .relative_eq(&other.matrix, epsilon, max_relative)
+            && self
+                .origin
+                .relative_eq(&other.origin, epsilon, max_relative)
+    }
+}
+// 0d8e4b6a ends here
@@ -0,0 +1,228 @@
+// [[file:../lattice.note::5e0b7c2d][5e0b7c2d]]
+use crate::hnf::hermite_normal_forms;
+use crate::strain::{polar_decomposition, Strain};
+use crate::surface::gauss_reduce;
+use crate::utils::*;
+use crate::Lattice;
+
+use vecfx::*;
+// 5e0b7c2d ends here
+
+// [[file:../lattice.note::91d4f3a6][91d4f3a6]]
+/// A coincidence of in-plane supercells of two lattices, as returned by
+/// [`Lattice::match_lattice`].
+#[derive(Debug, Clone, Copy)]
+pub struct LatticeMatch {
+    /// The supercell matrix of the first lattice in the convention of
+    /// [`Lattice::supercell`]. Only the in-plane vectors are transformed.
+    pub transform_a: [[isize; 3]; 3],
+    /// The supercell matrix of the second lattice.
+    pub transform_b: [[isize; 3]; 3],
+    /// The common lattice, i.e. the supercell of the first lattice.
+    pub lattice: Lattice,
+    /// The strain applied to the supercell of the second lattice to fit
+    /// the common lattice, in Cartesian coordinates of the first lattice.
+    pub strain: Strain,
+    /// The in-plane rotation in degrees of the second lattice relative to
+    /// the first one.
+    pub angle: f64,
+    /// The area of the common in-plane cell.
+    pub area: f64,
+}
+
+// The rotation from Cartesian space into a frame with x along `a` and z
+// along the normal of the ab plane.
+fn plane_frame(lat: &Lattice) -> Matrix3f {
+    let e1 = lat.vector_a().normalize();
+    let n = lat.vector_a().cross(&lat.vector_b()).normalize();
+    let e2 = n.cross(&e1);
+    Matrix3f::from_rows(&[e1.transpose(), e2.transpose(), n.transpose()])
+}
+
+// All in-plane superlattices of index `n`, with the in-plane vectors
+// Gauss-reduced and right-handed with respect to the ab plane.
+fn plane_superlattices(lat: &Lattice, n: usize) -> Vec<[[isize; 3]; 3]> {
+    let cart = |x: [isize; 3]| lat.matrix() * Vector3f::from(x.map(|i| i as f64));
+    let normal = lat.vector_a().cross(&lat.vector_b());
+    hermite_normal_forms(n)
+        .filter(|h| h[2] == [0, 0, 1])
+        .map(|h| {
+            let (mut u, mut v) = gauss_reduce(lat, h[0], h[1]);
+            if cart(u).cross(&cart(v)).dot(&normal) < 0.0 {
+                std::mem::swap(&mut u, &mut v);
+            }
+            [u, v, [0, 0, 1]]
+        })
+        .collect()
+}
+
+// Right-handed bases of the same plane lattice, equivalent to a reduced
+// basis up to the ambiguities of the reduction.
+fn equivalent_bases(m: [[isize; 3]; 3]) -> impl Iterator<Item = [[isize; 3]; 3]> {
+    let coeffs = [
+        (1, 0),
+        (0, 1),
+        (1, 1),
+        (1, -1),
+        (-1, 0),
+        (0, -1),
+        (-1, -1),
+        (-1, 1),
+    ];
+    let [u, v, w] = m;
+    let comb = move |(i, j): (isize, isize)| [0, 1, 2].map(|k| i * u[k] + j * v[k]);
+    coeffs
+        .into_iter()
+        .flat_map(move |p| coeffs.into_iter().map(move |q| (p, q)))
+        .filter(|&(p, q)| p.0 * q.1 - p.1 * q.0 == 1)
+        .map(move |(p, q)| [comb(p), comb(q), w])
+}
+
+impl Lattice {
+    /// Search for in-plane supercells of `self` and `other` whose `a` and
+    /// `b` vectors coincide within `max_strain` (Zur-McGill algorithm).
+    /// Only supercells of `self` with area up to `max_area` are
+    /// considered. For surfaces, use the lattices of [`Slab`]s, whose `a`
+    /// and `b` vectors span the surface plane.
+    ///
+    /// The supercell of `other` is rotated in plane and strained to fit the
+    /// supercell of `self`, which is kept unchanged. `max_strain` bounds
+    /// the principal values of the applied strain. The results are sorted
+    /// by area, and then by the magnitude of the strain.
+    ///
+    /// [`Slab`]: crate::Slab
+    pub fn match_lattice(
+        &self,
+        other: &Lattice,
+        max_area: f64,
+        max_strain: f64,
+    ) -> Vec<LatticeMatch> {
+        assert!(max_area > 0.0, "invalid max area: {max_area}");
+        assert!(
+            (0.0..1.0).contains(&max_strain),
+            "invalid max strain: {max_strain}"
+        );
+
+        let area = |lat: &Lattice| lat.vector_a().cross(&lat.vector_b()).norm();
+        let (area_a, area_b) = (area(self), area(other));
+        let (qa, qb) = (plane_frame(self), plane_frame(other));
+        let (lo, hi) = ((1.0 - max_strain).powi(2), (1.0 + max_strain).powi(2));
+
+        let mut matches = vec![];
+        for n in (1..).take_while(|&n| n as f64 * area_a <= max_area) {
+            let m_lo = (n as f64 * area_a / (hi * area_b)).ceil().max(1.0) as usize;
+            let m_hi = (n as f64 * area_a / (lo * area_b)).floor() as usize;
+            if m_lo > m_hi {
+                continue;
+            }
+            let supers_a = plane_superlattices(self, n);
+            let supers_b: Vec<_> = (m_lo..=m_hi)
+                .flat_map(|m| plane_superlattices(other, m))
+                .collect();
+            for &ta in &supers_a {
+                let lat = self.supercell(ta);
+                let mut fa = qa * lat.matrix();
+                fa.set_column(2, &Vector3f::z());
+                for &tb in &supers_b {
+                    // the best fit among equivalent bases
+                    let best = equivalent_bases(tb)
+                        .map(|tb| {
+                            let mut fb = qb * get_supercell_matrix(&other.matrix(), tb);
+                            fb.set_column(2, &Vector3f::z());
+                            let (r, u) = polar_decomposition(fa * get_inv_matrix(&fb));
+                            let e = u - Matrix3f::identity();
+                            let x = e.symmetric_eigen().eigenvalues.amax();
+                            (tb, r, e, x)
+                        })
+                        .min_by(|a, b| a.3.total_cmp(&b.3))
+                        .unwrap();
+                    let (tb, r, e, x) = best;
+                    if x <= max_strain {
+                        // strain in the frame of `self`: R U = (I + R e Rᵀ) R
+                        let e = qa.transpose() * r * e * r.transpose() * qa;
+                        matches.push(LatticeMatch {
+                            transform_a: ta,
+                            transform_b: tb,
+                            lattice: lat,
+                            strain: Strain::new(e),
+                            angle: r[(1, 0)].atan2(r[(0, 0)]).to_degrees(),
+                            area: n as f64 * area_a,
+                        });
+                    }
+                }
+            }
+        }
+        matches.sort_by(|x, y| {
+            x.area.total_cmp(&y.area).then(
+                x.strain
+                    .matrix()
+                    .norm()
+                    .total_cmp(&y.strain.matrix().norm()),
+            )
+        });
+        matches
+    }
+}
+// 91d4f3a6 ends here
+
+// [[file:../lattice.note::c82a5e17][c82a5e17]]
+#[test]
+fn test_lattice_match() {
+    use approx::*;
+
+    // check that the strained and rotated supercell of `b` fits the common lattice
+    let check = |a: &Lattice, b: &Lattice, m: &LatticeMatch| {
+        let (qa, qb) = (plane_frame(a), plane_frame(b));
+        let r =
+            vecfx::nalgebra::Rotation3::from_axis_angle(&Vector3f::z_axis(), m.angle.to_radians())
+                .into_inner();
+        let f = (Matrix3f::identity() + m.strain.matrix()) * qa.transpose() * r * qb;
+        let lat_b = b.supercell(m.transform_b);
+        assert_relative_eq!(f * lat_b.vector_a(), m.lattice.vector_a(), epsilon = 1e-6);
+        assert_relative_eq!(f * lat_b.vector_b(), m.lattice.vector_b(), epsilon = 1e-6);
+        assert_relative_eq!(m.lattice.vector_c(), a.vector_c(), epsilon = 1e-6);
+    };
+
+    // square lattices of 3 and 4 Angstrom: 4x4 on 3x3, up to rotation
+    let a = Lattice::from_params(3.0, 3.0, 10.0, 90.0, 90.0, 90.0);
+    let b = Lattice::from_params(4.0, 4.0, 10.0, 90.0, 90.0, 90.0);
+    let matches = a.match_lattice(&b, 150.0, 1e-6);
+    assert!(!matches.is_empty());
+    for m in &matches {
+        assert_relative_eq!(m.area, 144.0, epsilon = 1e-6);
+        assert_relative_eq!(m.strain.matrix(), Matrix3f::zeros(), epsilon = 1e-6);
+        assert_relative_eq!((m.angle / 90.0).round() * 90.0, m.angle, epsilon = 1e-6);
+        assert_eq!(imatrix_det(m.transform_a), 16);
+        assert_eq!(imatrix_det(m.transform_b), 9);
+        check(&a, &b, m);
+    }
+    assert!(a.match_lattice(&b, 140.0, 1e-6).is_empty());
+
+    // graphene on hexagonal boron nitride, in different orientations
+    let a = Lattice::from_params(2.46, 2.46, 10.0, 90.0, 90.0, 120.0);
+    let mut b = Lattice::from_params(2.504, 2.504, 6.66, 90.0, 90.0, 60.0);
+    b.rotate_about([1.0, 1.0, 0.0], 30.0);
+    let matches = a.match_lattice(&b, 20.0, 0.02);
+    let m = &matches[0];
+    assert_relative_eq!(m.area, a.volume() / 10.0, epsilon = 1e-6);
+    let x = 2.46 / 2.504 - 1.0;
+    assert_relative_eq!(m.strain.matrix().trace(), 2.0 * x, epsilon = 1e-6);
+    check(&a, &b, m);
+    for m in &matches {
+        check(&a, &b, m);
+        assert!(m.strain.matrix().symmetric_eigen().eigenvalues.amax() <= 0.02);
+    }
+    assert!(a.match_lattice(&b, 20.0, 0.01).is_empty());
+
+    // (111) surface of a cubic lattice on a hexagonal lattice
+    let slab = Lattice::from_params(4.0, 4.0, 4.0, 90.0, 90.0, 90.0).surface(1, 1, 1, 2, 10.0);
+    let a = slab.lattice();
+    let b = Lattice::from_params(3.2, 3.2, 5.0, 90.0, 90.0, 120.0);
+    let matches = a.match_lattice(&b, 100.0, 0.03);
+    assert!(!matches.is_empty());
+    for m in &matches {
+        assert!(m.area <= 100.0);
+        check(&a, &b, m);
+    }
+}
+// c82a5e17 ends here
@@ -0,0 +1,163 @@
+// [[file:../lattice.note::5d9a2e71][5d9a2e71]]
+use crate::Lattice;
+// 5d9a2e71 ends here
+
+// [[file:../lattice.note::0f3c8b96][0f3c8b96]]
+/// Cardinal B-spline of order `n`, which is nonzero on `(0, n)`.
+///
+/// Reference
+/// ---------
+/// - Essmann, U.; Perera, L.; Berkowitz, M. L.; Darden, T.; Lee, H.;
+///   Pedersen, L. G. J. Chem. Phys. 1995, 103, 8577-8593.
+fn bspline(n: usize, u: f64) -> f64 {
+    if n == 2 {
+        (1.0 - (u - 1.0).abs()).max(0.0)
+    } else {
+        let m = (n - 1) as f64;
+        u / m * bspline(n - 1, u) + (n as f64 - u) / m * bspline(n - 1, u - 1.0)
+    }
+}
+
+// Return the grid indices and B-spline weights on one direction for scaled
+// coordinate `u` in grid units. The spline is centered at `u`, so that the
+// first moment of the weights is exact.
+fn bspline_weights(order: usize, u: f64, n: usize) -> impl Iterator<Item = (usize, f64)> {
+    let half = 0.5 * order as f64;
+    let k0 = (u - half).ceil() as isize;
+    (k0..k0 + order as isize).map(move |k| {
+        let w = bspline(order, u - k as f64 + half);
+        (k.rem_euclid(n as isize) as usize, w)
+    })
+}
+
+impl Lattice {
+    // Return flat grid indices and weights for point `p` in Cartesian
+    // coordinates.
+    fn grid_weights(&self, grid: [usize; 3], order: usize, p: [f64; 3]) -> Vec<(usize, f64)> {
+        assert!(
+            (4..=8).contains(&order),
+            "unsupported B-spline order: {order}"
+        );
+        let f = self.wrap_frac(self.to_frac(p));
+        let [nx, ny, nz] = grid;
+        let wx: Vec<_> = bspline_weights(order, f.x * nx as f64, nx).collect();
+        let wy: Vec<_> = bspline_weights(order, f.y * ny as f64, ny).collect();
+        let wz: Vec<_> = bspline_weights(order, f.z * nz as f64, nz).collect();
+        let mut weights = Vec::with_capacity(order * order * order);
+        for &(i, a) in wx.iter() {
+            for &(j, b) in wy.iter() {
+                for &(k, c) in wz.iter() {
+                    weights.push(((i * ny + j) * nz + k, a * b * c));
+                }
+            }
+        }
+        weights
+    }
+
+    /// Spread point `values` at Cartesian `positions` onto a periodic grid
+    /// of size `grid` using B-splines of `order` (4 to 8). Grid point (i,
+    /// j, k) is located at fractional coordinates (i/nx, j/ny, k/nz) and
+    /// stored at flat index `(i * ny + j) * nz + k`.
+    ///
+    /// The sum over the grid equals the sum of `values`. Divide by the voxel
+    /// volume `V/(nx ny nz)` to obtain a volumetric density.
+    pub fn spread_to_grid(
+        &self,
+        grid: [usize; 3],
+        order: usize,
+        positions: &[[f64; 3]],
+        values: &[f64],
+    ) -> Vec<f64> {
+        assert_eq!(
+            positions.len(),
+            values.len(),
+            "positions and values mismatch"
+        );
+        let mut data = vec![0.0; grid.iter().product()];
+        for (&p, &v) in positions.iter().zip(values) {
+            for (i, w) in self.grid_weights(grid, order, p) {
+                data[i] += w * v;
+            }
+        }
+        data
+    }
+
+    /// Interpolate periodic grid `data` of size `grid` at Cartesian
+    /// `positions` using B-splines of `order` (4 to 8). This is the inverse
+    /// operation (the adjoint) of [`Lattice::spread_to_grid`], with the
+    /// same grid layout.
+    pub fn gather_from_grid(
+        &self,
+        grid: [usize; 3],
+        order: usize,
+        data: &[f64],
+        positions: &[[f64; 3]],
+    ) -> Vec<f64> {
+        assert_eq!(
+            data.len(),
+            grid.iter().product::<usize>(),
+            "invalid grid data size"
+        );
+        positions
+            .iter()
+            .map(|&p| {
+                self.grid_weights(grid, order, p)
+                    .into_iter()
+                    .map(|(i, w)| w * data[i])
+                    .sum()
+            })
+            .collect()
+    }
+}
+// 0f3c8b96 ends here
+
+// [[file:../lattice.note::b7e05c2d][b7e05c2d]]
+#[test]
+fn test_bspline_spreading() {
+    use std::f64::consts::PI;
+    use vecfx::approx::*;
+
+    for n in 4..=8 {
+        // partition of unity and exact first moment
+        let u = 3.37;
+        let w: Vec<_> = bspline_weights(n, u, 100).collect();
+        let s: f64 = w.iter().map(|x| x.1).sum();
+        assert_relative_eq!(s, 1.0, epsilon = 1e-12);
+        let m: f64 = w.iter().map(|&(k, w)| k as f64 * w).sum();
+        assert_relative_eq!(m, u, epsilon = 1e-12);
+    }
+
+    let lat = Lattice::from_params(5.0, 6.0, 7.0, 80.0, 100.0, 110.0);
+    let grid = [16, 18, 20];
+    let positions = [[0.1, 0.2, 0.3], [4.5, -2.0, 11.0], [3.0, 0.5, 2.5]];
+    let values = [1.0, -0.5, 2.0];
+    for order in [4, 6, 8] {
+        let data = lat.spread_to_grid(grid, order, &positions, &values);
+        assert_relative_eq!(data.iter().sum::<f64>(), 2.5, epsilon = 1e-12);
+
+        // adjoint: <gather(x), v> = <x, spread(v)>
+        let x: Vec<_> = (0..data.len()).map(|i| (i as f64 * 0.37).sin()).collect();
+        let g = lat.gather_from_grid(grid, order, &x, &positions);
+        let lhs: f64 = g.iter().zip(&values).map(|(a, b)| a * b).sum();
+        let rhs: f64 = x.iter().zip(&data).map(|(a, b)| a * b).sum();
+        assert_relative_eq!(lhs, rhs, epsilon = 1e-12);
+    }
+
+    // interpolate a smooth periodic function
+    let [nx, ny, nz] = grid;
+    let mut data = vec![0.0; nx * ny * nz];
+    for i in 0..nx {
+        for j in 0..ny {
+            for k in 0..nz {
+                data[(i * ny + j) * nz + k] = (2.0 * PI * i as f64 / nx as f64).cos();
+            }
+        }
+    }
+    let p = lat.to_cart([0.31, 0.52, 0.77]);
+    let v = lat.gather_from_grid(grid, 8, &data, &[p.into()])[0];
+    // B-spline interpolation damps the Fourier component slightly
+    assert_relative_eq!(v, (2.0 * PI * 0.31).cos(), epsilon = 0.05);
+    let v4 = lat.gather_from_grid(grid, 4, &data, &[p.into()])[0];
+    assert!((v - v4).abs() < 0.05);
+}
+// b7e05c2d ends here
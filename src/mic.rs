@@ -1,6 +1,7 @@
 // [[file:../lattice.note::*imports][imports:1]]
-use vecfx::*;
+#[cfg(test)]
 use approx::*;
+use vecfx::*;
 
 use crate::Lattice;
 // imports:1 ends here
@@ -12,20 +13,20 @@ impl Lattice {
     /// Reference
     /// ---------
     /// - Tuckerman, M. E. Statistical Mechanics: Theory and Molecular
-    /// Simulation, 1 edition.; Oxford University Press: Oxford ; New York,
-    /// 2010.
+    ///   Simulation, 1 edition.; Oxford University Press: Oxford ; New York,
+    ///   2010.
     pub(crate) fn apply_mic_tuckerman(&self, p: [f64; 3]) -> Vector3f {
         // apply minimum image convention on the scaled coordinates
-        let mut fcoords = self.to_frac(p);
+        let mut fcoords = self.inv_matrix * Vector3f::from(p);
 
         let mut image = [1.0; 3];
         for i in 0..3 {
-            image[i] = -1.0 * fcoords[i].round();
+            image[i] = -fcoords[i].round();
             fcoords[i] += image[i];
         }
 
         // transform back to cartesian coordinates
-        self.to_cart(fcoords)
+        self.matrix * fcoords
     }
 
     /// Return the distance between two points computed using the minimum image
@@ -34,8 +35,9 @@ impl Lattice {
     /// Reference
     /// ---------
     /// - Tuckerman, M. E. Statistical Mechanics: Theory and Molecular
-    /// Simulation, 1 edition.; Oxford University Press: Oxford ; New York,
-    /// 2010.
+    ///   Simulation, 1 edition.; Oxford University Press: Oxford ; New York,
+    ///   2010.
+    #[allow(dead_code)]
     pub(crate) fn distance_tuckerman(&self, pi: [f64; 3], pj: [f64; 3]) -> f64 {
         let pij = [pj[0] - pi[0], pj[1] - pi[1], pj[2] - pi[2]];
 
@@ -49,11 +51,12 @@ impl Lattice {
 impl Lattice {
     /// Return the minimal number of images for neighborhood search on each cell
     /// direction within cutoff radius
-    fn n_min_images(&self, radius: f64) -> [isize; 3] {
+    pub(crate) fn n_min_images(&self, radius: f64) -> [isize; 3] {
         let mut ns = [0; 3];
 
+        // widths are negative for left-handed cells
         for (i, &w) in self.widths().iter().enumerate() {
-            let n = (radius / w).ceil();
+            let n = (radius / w.abs()).ceil();
             ns[i] = n as isize;
         }
 
@@ -63,6 +66,7 @@ impl Lattice {
     /// Return the shortest distance between `pi` (point i) and the periodic
     /// images of `pj` (point j). This algorithm will loop over all relevant
     /// images
+    #[allow(dead_code)]
     pub(crate) fn distance_brute_force(&self, pi: [f64; 3], pj: [f64; 3]) -> f64 {
         let v = Vector3f::from(pj) - Vector3f::from(pi);
         let pmic = self.apply_mic_brute_force(v.into());
@@ -104,22 +108,21 @@ impl Lattice {
         // search the MIC point with minimum length among relevant image points
         let relevant_points: Vec<Vector3f> = self
             .replicate(-na..=na, -nb..=nb, -nc..=nc)
-            .map(|image| p + self.to_cart(image))
+            .map(|image| p + self.matrix * image)
             .collect();
         let distances2: Vec<_> = relevant_points
             .iter()
             .map(|&p| Vector3f::from(p).norm_squared())
             .collect();
         let i = distances2.imin();
-        let pmin = relevant_points[i];
-
-        pmin.into()
+        relevant_points[i]
     }
 }
 // brute force:1 ends here
 
 // [[file:../lattice.note::*test][test:1]]
 #[test]
+#[allow(clippy::approx_constant)]
 fn test_mic_distance() {
     // Setup lattice
     // a = b = c = 4, alpha = beta = gamma = 60
@@ -187,6 +190,7 @@ fn test_mic_distance_2() {
 }
 
 #[test]
+#[allow(clippy::bool_assert_comparison)]
 fn test_neighborhood() {
     let lat = Lattice::new([[18.256, 0., 0.], [0., 20.534, 0.], [0., 0., 15.084]]);
     assert_eq!(true, lat.is_orthorhombic());
@@ -196,4 +200,25 @@ fn test_neighborhood() {
     assert_eq!([2, 1, 2], lat.n_min_images(20.));
     assert_eq!([2, 2, 2], lat.n_min_images(20.6));
 }
+
+#[test]
+fn test_wrap_wigner_seitz() {
+    let lat0 = Lattice::new([[5.0, 0.0, 0.0], [4.0, 3.0, 0.0], [0.0, 0.0, 6.0]]);
+    // mic vectors should not depend on the lattice origin
+    let mut lat = lat0;
+    lat.set_origin([1.0, 2.0, 3.0]);
+    let v = [-0.94112, -4.34823, 2.53058];
+    assert_relative_eq!(lat.apply_mic(v), lat0.apply_mic(v), epsilon = 1e-8);
+
+    let center = Vector3f::from([1.0, 1.0, 1.0]);
+    let p = Vector3f::from([8.5, 3.5, -4.0]);
+    let q = lat.wrap_wigner_seitz(p, center);
+    // the wrapped point is a periodic image of the original one
+    let f = lat.inv_matrix() * (q - p);
+    assert_relative_eq!(f, f.map(|x| x.round()), epsilon = 1e-8);
+    // and it is the closest image to the center
+    assert_relative_eq!((q - center).norm(), lat.distance(center, p), epsilon = 1e-8);
+    let ws = lat.wigner_seitz_cell();
+    assert!(ws.contains(q - center, 1e-8));
+}
 // test:1 ends here
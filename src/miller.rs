@@ -0,0 +1,223 @@
+// [[file:../lattice.note::c47d0a19][c47d0a19]]
+use crate::utils::*;
+use crate::Lattice;
+
+use gchemol_gut::prelude::*;
+use vecfx::*;
+// c47d0a19 ends here
+
+// [[file:../lattice.note::71be29d5][71be29d5]]
+fn to_vector3f(x: [isize; 3]) -> Vector3f {
+    x.map(|i| i as f64).into()
+}
+
+/// Return the zone axis `[uvw]` shared by planes `p1` and `p2` given in
+/// Miller indices, i.e. the lattice direction lying in both planes. The
+/// result is reduced to the smallest integers.
+pub fn zone_axis(p1: [isize; 3], p2: [isize; 3]) -> [isize; 3] {
+    let [h1, k1, l1] = p1;
+    let [h2, k2, l2] = p2;
+    let uvw = [k1 * l2 - l1 * k2, l1 * h2 - h1 * l2, h1 * k2 - k1 * h2];
+    let g = gcd(gcd(uvw[0], uvw[1]), uvw[2]);
+    assert_ne!(g, 0, "parallel planes: {p1:?}, {p2:?}");
+    uvw.map(|x| x / g)
+}
+
+impl Lattice {
+    /// Return the reciprocal lattice vector of the (`h`, `k`, `l`) plane in
+    /// Cartesian coordinates, without the factor of 2π. Its length is the
+    /// inverse of the interplanar spacing.
+    fn plane_vector(&self, h: isize, k: isize, l: isize) -> Vector3f {
+        assert!(
+            [h, k, l] != [0, 0, 0],
+            "invalid Miller indices: ({h} {k} {l})"
+        );
+        self.inv_matrix.transpose() * to_vector3f([h, k, l])
+    }
+
+    /// Return the interplanar spacing of the (`h`, `k`, `l`) planes.
+    pub fn d_spacing(&self, h: isize, k: isize, l: isize) -> f64 {
+        1.0 / self.plane_vector(h, k, l).norm()
+    }
+
+    /// Return the unit normal vector of the (`h`, `k`, `l`) plane in
+    /// Cartesian coordinates.
+    pub fn plane_normal(&self, h: isize, k: isize, l: isize) -> Vector3f {
+        self.plane_vector(h, k, l).normalize()
+    }
+
+    /// Return the Cartesian vector of lattice direction [`u`, `v`, `w`].
+    pub fn direction_to_cart(&self, uvw: [isize; 3]) -> Vector3f {
+        self.matrix * to_vector3f(uvw)
+    }
+
+    /// Return the angle in degrees between planes `p1` and `p2` given in
+    /// Miller indices.
+    pub fn angle_between_planes(&self, p1: [isize; 3], p2: [isize; 3]) -> f64 {
+        let [h1, k1, l1] = p1;
+        let [h2, k2, l2] = p2;
+        let n1 = self.plane_vector(h1, k1, l1);
+        let n2 = self.plane_vector(h2, k2, l2);
+        n1.angle(&n2).to_degrees()
+    }
+
+    /// Return the angle in degrees between lattice directions `d1` and
+    /// `d2` given as `[uvw]`.
+    pub fn angle_between_directions(&self, d1: [isize; 3], d2: [isize; 3]) -> f64 {
+        let v1 = self.direction_to_cart(d1);
+        let v2 = self.direction_to_cart(d2);
+        v1.angle(&v2).to_degrees()
+    }
+}
+// 71be29d5 ends here
+
+// [[file:../lattice.note::f2a4c861][f2a4c861]]
+/// Convert plane (`h`, `k`, `l`) into Miller-Bravais four-index notation
+/// (`h`, `k`, `i`, `l`) for hexagonal lattices, with `i = -(h + k)`.
+pub fn plane_to_hkil(hkl: [isize; 3]) -> [isize; 4] {
+    let [h, k, l] = hkl;
+    [h, k, -(h + k), l]
+}
+
+/// Convert plane (`h`, `k`, `i`, `l`) in Miller-Bravais four-index notation
+/// into three-index notation.
+pub fn plane_from_hkil(hkil: [isize; 4]) -> Result<[isize; 3]> {
+    let [h, k, i, l] = hkil;
+    ensure!(i == -(h + k), "invalid Miller-Bravais indices: {hkil:?}");
+    Ok([h, k, l])
+}
+
+/// Convert lattice direction [`U`, `V`, `W`] into Miller-Bravais four-index
+/// notation [`u`, `v`, `t`, `w`] for hexagonal lattices. The result is
+/// reduced to the smallest integers.
+pub fn direction_to_uvtw(uvw: [isize; 3]) -> [isize; 4] {
+    let [u, v, w] = uvw;
+    // u = (2U - V)/3, v = (2V - U)/3, t = -(U + V)/3, w = W
+    let uvtw = [2 * u - v, 2 * v - u, -(u + v), 3 * w];
+    let g = uvtw.iter().fold(0, |g, &x| gcd(g, x));
+    assert_ne!(g, 0, "invalid direction: {uvw:?}");
+    uvtw.map(|x| x / g)
+}
+
+/// Convert lattice direction [`u`, `v`, `t`, `w`] in Miller-Bravais
+/// four-index notation into three-index notation. The result is reduced to
+/// the smallest integers.
+pub fn direction_from_uvtw(uvtw: [isize; 4]) -> Result<[isize; 3]> {
+    let uvw = get_direction_from_uvtw(uvtw)?;
+    let g = gcd(gcd(uvw[0], uvw[1]), uvw[2]);
+    ensure!(g != 0, "invalid direction: {uvtw:?}");
+    Ok(uvw.map(|x| x / g))
+}
+
+// U = u - t, V = v - t, W = w, without reduction
+fn get_direction_from_uvtw(uvtw: [isize; 4]) -> Result<[isize; 3]> {
+    let [u, v, t, w] = uvtw;
+    ensure!(t == -(u + v), "invalid Miller-Bravais indices: {uvtw:?}");
+    Ok([u - t, v - t, w])
+}
+
+impl Lattice {
+    fn check_hexagonal(&self) -> Result<()> {
+        ensure!(
+            self.is_hexagonal(1e-4),
+            "Miller-Bravais indices require a hexagonal cell: {:?} {:?}",
+            self.lengths(),
+            self.angles()
+        );
+        Ok(())
+    }
+
+    /// Return the interplanar spacing of the (`h`, `k`, `i`, `l`) planes in
+    /// a hexagonal lattice.
+    pub fn d_spacing_hkil(&self, hkil: [isize; 4]) -> Result<f64> {
+        self.check_hexagonal()?;
+        let [h, k, l] = plane_from_hkil(hkil)?;
+        Ok(self.d_spacing(h, k, l))
+    }
+
+    /// Return the Cartesian vector of lattice direction [`u`, `v`, `t`,
+    /// `w`] in a hexagonal lattice, i.e. `u a1 + v a2 + t a3 + w c` with
+    /// `a3 = -(a1 + a2)`.
+    pub fn direction_uvtw_to_cart(&self, uvtw: [isize; 4]) -> Result<Vector3f> {
+        self.check_hexagonal()?;
+        let uvw = get_direction_from_uvtw(uvtw)?;
+        Ok(self.direction_to_cart(uvw))
+    }
+}
+// f2a4c861 ends here
+
+// [[file:../lattice.note::e6f3258a][e6f3258a]]
+#[test]
+fn test_miller_geometry() {
+    use approx::*;
+
+    let lat = Lattice::from_params(4.0, 4.0, 4.0, 90.0, 90.0, 90.0);
+    assert_relative_eq!(lat.d_spacing(1, 1, 1), 4.0 / 3f64.sqrt(), epsilon = 1e-8);
+    assert_relative_eq!(lat.d_spacing(2, 0, 0), 2.0, epsilon = 1e-8);
+    let angle = lat.angle_between_planes([1, 0, 0], [1, 1, 1]);
+    assert_relative_eq!(angle, 54.7356, epsilon = 1e-4);
+    let angle = lat.angle_between_directions([1, 1, 0], [1, 1, 1]);
+    assert_relative_eq!(angle, 35.2644, epsilon = 1e-4);
+    assert_eq!(zone_axis([1, 1, 0], [1, -1, 0]), [0, 0, -1]);
+    assert_eq!(zone_axis([2, 0, 0], [0, 2, 0]), [0, 0, 1]);
+    let n = lat.plane_normal(1, 1, 0);
+    assert_relative_eq!(
+        n,
+        Vector3f::from([1.0, 1.0, 0.0]).normalize(),
+        epsilon = 1e-8
+    );
+
+    // hexagonal cell: 1/d^2 = 4/3 (h^2 + hk + k^2)/a^2 + l^2/c^2
+    let (a, c) = (3.0, 5.0);
+    let lat = Lattice::from_params(a, a, c, 90.0, 90.0, 120.0);
+    let (h, k, l) = (1, 2, 3);
+    let d = (4.0 / 3.0 * (h * h + h * k + k * k) as f64 / (a * a) + (l * l) as f64 / (c * c))
+        .sqrt()
+        .recip();
+    assert_relative_eq!(lat.d_spacing(h, k, l), d, epsilon = 1e-8);
+    // the zone axis is perpendicular to the normals of both planes
+    let uvw = zone_axis([1, 0, 1], [0, 1, 2]);
+    let v = lat.direction_to_cart(uvw);
+    assert_relative_eq!(v.dot(&lat.plane_normal(1, 0, 1)), 0.0, epsilon = 1e-8);
+    assert_relative_eq!(v.dot(&lat.plane_normal(0, 1, 2)), 0.0, epsilon = 1e-8);
+}
+
+#[test]
+fn test_miller_bravais() {
+    use approx::*;
+
+    assert_eq!(plane_to_hkil([1, 0, 0]), [1, 0, -1, 0]);
+    assert_eq!(plane_to_hkil([1, 1, 2]), [1, 1, -2, 2]);
+    assert_eq!(plane_from_hkil([1, 1, -2, 2]).unwrap(), [1, 1, 2]);
+    assert!(plane_from_hkil([1, 1, 2, 2]).is_err());
+    assert_eq!(direction_to_uvtw([1, 0, 0]), [2, -1, -1, 0]);
+    assert_eq!(direction_to_uvtw([0, 0, 1]), [0, 0, 0, 1]);
+    assert_eq!(direction_to_uvtw([1, 1, 0]), [1, 1, -2, 0]);
+    assert_eq!(direction_from_uvtw([2, -1, -1, 0]).unwrap(), [1, 0, 0]);
+    assert_eq!(direction_from_uvtw([1, 1, -2, 3]).unwrap(), [1, 1, 1]);
+    assert!(direction_from_uvtw([1, 1, 1, 0]).is_err());
+    for uvw in [[1, 2, 3], [-1, 0, 2], [2, 1, 0]] {
+        assert_eq!(direction_from_uvtw(direction_to_uvtw(uvw)).unwrap(), uvw);
+    }
+
+    let (a, c) = (3.0, 5.0);
+    let lat = Lattice::from_params(a, a, c, 90.0, 90.0, 120.0);
+    let d = lat.d_spacing_hkil([1, 0, -1, 0]).unwrap();
+    assert_relative_eq!(d, a * 3f64.sqrt() / 2.0, epsilon = 1e-8);
+    let d = lat.d_spacing_hkil([1, 1, -2, 2]).unwrap();
+    assert_relative_eq!(d, lat.d_spacing(1, 1, 2), epsilon = 1e-8);
+    // [2 -1 -1 0] = 3 a1
+    let v = lat.direction_uvtw_to_cart([2, -1, -1, 0]).unwrap();
+    assert_relative_eq!(v, lat.vector_a() * 3.0, epsilon = 1e-8);
+    // prismatic directions are normal to the planes with the same indices
+    for hkil in [[1, 0, -1, 0], [1, 1, -2, 0], [-1, 2, -1, 0]] {
+        let [h, k, l] = plane_from_hkil(hkil).unwrap();
+        let v = lat.direction_uvtw_to_cart(hkil).unwrap().normalize();
+        assert_relative_eq!(v, lat.plane_normal(h, k, l), epsilon = 1e-8);
+    }
+
+    // not a hexagonal cell
+    let lat = Lattice::from_params(a, a, c, 90.0, 90.0, 90.0);
+    assert!(lat.d_spacing_hkil([1, 0, -1, 0]).is_err());
+}
+// e6f3258a ends here
@@ -0,0 +1,151 @@
+// [[file:../lattice.note::2e7b5c90][2e7b5c90]]
+use crate::Lattice;
+
+use vecfx::nalgebra::Rotation3;
+use vecfx::*;
+// 2e7b5c90 ends here
+
+// [[file:../lattice.note::95c1f3a8][95c1f3a8]]
+/// Conventions for the standard orientation of lattice vectors in
+/// Cartesian space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    /// Lattice vector a along x and b in the xy plane, i.e. the lattice
+    /// matrix (with lattice vectors as columns) is upper triangular, as in
+    /// [`Lattice::from_params`].
+    AParallelX,
+    /// Lattice vector c along z and b in the yz plane, i.e. the lattice
+    /// matrix is lower triangular.
+    CParallelZ,
+}
+
+impl Lattice {
+    /// Return the lattice rigidly rotated into the standard orientation of
+    /// `convention`, and the rotation applied, so that Cartesian positions
+    /// can be rotated accordingly. The cell origin is rotated too. Lengths
+    /// and angles are preserved, and fractional coordinates are unchanged.
+    pub fn standard_orientation(&self, convention: Orientation) -> (Lattice, Rotation3<f64>) {
+        // Gram-Schmidt orthogonalization, i.e. QR decomposition
+        let (e1, e2, e3) = match convention {
+            Orientation::AParallelX => {
+                let e1 = self.vector_a().normalize();
+                let b = self.vector_b();
+                let e2 = (b - e1 * e1.dot(&b)).normalize();
+                (e1, e2, e1.cross(&e2))
+            }
+            Orientation::CParallelZ => {
+                let e3 = self.vector_c().normalize();
+                let b = self.vector_b();
+                let e2 = (b - e3 * e3.dot(&b)).normalize();
+                (e2.cross(&e3), e2, e3)
+            }
+        };
+        let r = Matrix3f::from_rows(&[e1.transpose(), e2.transpose(), e3.transpose()]);
+        let rotation = Rotation3::from_matrix_unchecked(r);
+
+        let mut lattice = *self;
+        lattice.rotate(rotation);
+        (lattice, rotation)
+    }
+
+    /// Rigidly rotate lattice vectors and the cell origin by rotation
+    /// matrix `r` about the Cartesian origin.
+    pub fn rotate<T: Into<Matrix3f>>(&mut self, r: T) {
+        let r = r.into();
+        let d = r.transpose() * r - Matrix3f::identity();
+        assert!(
+            d.amax() < 1e-6 && r.determinant() > 0.0,
+            "invalid rotation matrix: {r}"
+        );
+        self.matrix = r * self.matrix;
+        self.inv_matrix *= r.transpose();
+        self.origin = r * self.origin;
+    }
+
+    /// Rigidly rotate the lattice by `angle` in degrees about `axis`
+    /// through the Cartesian origin, counterclockwise when looking down
+    /// the axis.
+    pub fn rotate_about<T: Into<Vector3f>>(&mut self, axis: T, angle: f64) {
+        let axis = axis.into();
+        assert!(axis.norm() > 0.0, "invalid rotation axis: {axis}");
+        let r = Rotation3::new(axis.normalize() * angle.to_radians());
+        self.rotate(r);
+    }
+}
+// 95c1f3a8 ends here
+
+// [[file:../lattice.note::c6a04e7d][c6a04e7d]]
+#[test]
+fn test_standard_orientation() {
+    use vecfx::approx::*;
+
+    let lat0 = Lattice::from_params(3.0, 4.0, 5.0, 80.0, 100.0, 70.0);
+    // an arbitrarily oriented copy
+    let r = Rotation3::new(Vector3f::new(0.3, -1.2, 0.7));
+    let mut lat = Lattice::from_matrix(r * lat0.matrix());
+    lat.set_origin([1.0, 2.0, 3.0]);
+
+    let (lat1, rotation) = lat.standard_orientation(Orientation::AParallelX);
+    assert_relative_eq!(lat1.matrix(), lat0.matrix(), epsilon = 1e-10);
+    assert_relative_eq!(rotation.matrix().determinant(), 1.0, epsilon = 1e-10);
+    assert_relative_eq!(
+        lat1.inv_matrix(),
+        lat1.matrix().try_inverse().unwrap(),
+        epsilon = 1e-10
+    );
+    // co-rotated positions keep fractional coordinates
+    let p = Vector3f::new(0.5, -1.0, 2.0);
+    assert_relative_eq!(lat1.to_frac(rotation * p), lat.to_frac(p), epsilon = 1e-10);
+
+    let (lat2, _) = lat.standard_orientation(Orientation::CParallelZ);
+    let m = lat2.matrix();
+    assert_relative_eq!(
+        m.upper_triangle() - Matrix3f::from_diagonal(&m.diagonal()),
+        Matrix3f::zeros(),
+        epsilon = 1e-10
+    );
+    assert!(m.diagonal().iter().all(|&x| x > 0.0));
+    assert_relative_eq!(
+        lat2.lengths().as_slice(),
+        lat0.lengths().as_slice(),
+        epsilon = 1e-10
+    );
+    assert_relative_eq!(
+        lat2.angles().as_slice(),
+        lat0.angles().as_slice(),
+        epsilon = 1e-10
+    );
+}
+
+#[test]
+fn test_lattice_rotate() {
+    use vecfx::approx::*;
+
+    let mut lat = Lattice::from_params(3.0, 4.0, 5.0, 80.0, 100.0, 70.0);
+    lat.set_origin([1.0, 0.0, 0.0]);
+    let lat0 = lat;
+    lat.rotate_about([0.0, 0.0, 1.0], 90.0);
+    assert_relative_eq!(
+        lat.vector_a(),
+        Vector3f::new(0.0, 3.0, 0.0),
+        epsilon = 1e-10
+    );
+    assert_relative_eq!(lat.origin(), Vector3f::new(0.0, 1.0, 0.0), epsilon = 1e-10);
+    assert_relative_eq!(
+        lat.inv_matrix(),
+        lat.matrix().try_inverse().unwrap(),
+        epsilon = 1e-10
+    );
+    assert_relative_eq!(lat.volume(), lat0.volume(), epsilon = 1e-10);
+
+    // fractional coordinates follow the rotation
+    let r = Rotation3::new(Vector3f::new(0.3, -1.2, 0.7));
+    let mut lat = lat0;
+    lat.rotate(r);
+    let p = Vector3f::new(0.5, -1.0, 2.0);
+    assert_relative_eq!(lat.to_frac(r * p), lat0.to_frac(p), epsilon = 1e-10);
+    lat.rotate(r.inverse());
+    assert_relative_eq!(lat.matrix(), lat0.matrix(), epsilon = 1e-10);
+    assert_relative_eq!(lat.origin(), lat0.origin(), epsilon = 1e-10);
+}
+// c6a04e7d ends here
@@ -0,0 +1,165 @@
+// [[file:../lattice.note::0e5b7d24][0e5b7d24]]
+use crate::Lattice;
+
+use gchemol_gut::itertools::*;
+use std::f64::consts::PI;
+use vecfx::*;
+// 0e5b7d24 ends here
+
+// [[file:../lattice.note::93c1a6fe][93c1a6fe]]
+impl Lattice {
+    /// Return the reciprocal lattice in crystallographic convention, i.e.
+    /// `a* . a = 1` without the factor of 2π. The lattice vectors of the
+    /// reciprocal lattice are the rows of `inv_matrix()`.
+    pub fn reciprocal(&self) -> Lattice {
+        Lattice::from_matrix(self.inv_matrix.transpose())
+    }
+
+    /// Return the reciprocal lattice in physics convention, i.e. `a* . a =
+    /// 2π`.
+    pub fn reciprocal_2pi(&self) -> Lattice {
+        Lattice::from_matrix(self.inv_matrix.transpose() * 2.0 * PI)
+    }
+}
+// 93c1a6fe ends here
+
+// [[file:../lattice.note::d81b4f37][d81b4f37]]
+/// ħ^2/2m_e in eV Angstrom^2, for converting kinetic energy cutoff.
+const HBAR2_OVER_2ME: f64 = 3.80998212;
+
+/// A reciprocal lattice vector `G = h a* + k b* + l c*` in physics
+/// convention (with the factor of 2π).
+#[derive(Debug, Clone, Copy)]
+pub struct GVector {
+    /// integer indices (h, k, l)
+    pub hkl: [isize; 3],
+    /// Cartesian components in 1/Angstrom
+    pub g: Vector3f,
+}
+
+impl GVector {
+    /// The length of G vector in 1/Angstrom.
+    pub fn norm(&self) -> f64 {
+        self.g.norm()
+    }
+}
+
+// Return the smallest integer no less than `n` without prime factors
+// larger than 7, which is efficient for FFT.
+pub(crate) fn next_fft_size(n: usize) -> usize {
+    let is_smooth = |mut m: usize| {
+        for p in [2, 3, 5, 7] {
+            while m.is_multiple_of(p) {
+                m /= p;
+            }
+        }
+        m == 1
+    };
+    (n.max(1)..).find(|&m| is_smooth(m)).unwrap()
+}
+
+impl Lattice {
+    /// Return all reciprocal lattice vectors with |G| < `g_max` (in
+    /// 1/Angstrom), including G = 0, sorted by increasing length.
+    ///
+    /// The indices are bounded on each direction by the perpendicular
+    /// widths of the reciprocal cell, in the same way as the periodic images
+    /// for neighborhood search in real space.
+    pub fn g_vectors(&self, g_max: f64) -> Vec<GVector> {
+        let rec = self.reciprocal_2pi();
+        let [na, nb, nc] = rec.n_min_images(g_max);
+        let mut gs: Vec<_> = iproduct!(-na..=na, -nb..=nb, -nc..=nc)
+            .filter_map(|(h, k, l)| {
+                let g = rec.matrix() * Vector3f::from([h as f64, k as f64, l as f64]);
+                (g.norm() < g_max).then_some(GVector { hkl: [h, k, l], g })
+            })
+            .collect();
+        gs.sort_by(|a, b| a.norm().total_cmp(&b.norm()));
+        gs
+    }
+
+    /// Return all reciprocal lattice vectors for plane waves with kinetic
+    /// energy `ħ^2 |G|^2 / 2m_e` below `ecut` (in eV).
+    pub fn g_vectors_within_ecut(&self, ecut: f64) -> Vec<GVector> {
+        self.g_vectors((ecut / HBAR2_OVER_2ME).sqrt())
+    }
+
+    /// Return the recommended FFT grid size containing the sphere of
+    /// reciprocal lattice vectors with |G| < `g_max`. On each direction the
+    /// size is at least `2 n + 1` for the largest index `n` in the sphere,
+    /// rounded up to a number with only prime factors 2, 3, 5 and 7.
+    ///
+    /// For densities built from wave functions with cutoff `g_max`, use `2
+    /// g_max` instead.
+    pub fn fft_grid_size(&self, g_max: f64) -> [usize; 3] {
+        self.reciprocal_2pi()
+            .widths()
+            .map(|w| next_fft_size(2 * (g_max / w.abs()).floor() as usize + 1))
+    }
+}
+// d81b4f37 ends here
+
+// [[file:../lattice.note::5a2e80b4][5a2e80b4]]
+#[test]
+fn test_reciprocal_lattice() {
+    use approx::*;
+    use vecfx::*;
+
+    let lat = Lattice::from_params(3.0, 4.0, 5.0, 80.0, 100.0, 70.0);
+    let rec = lat.reciprocal();
+    let m = lat.matrix().transpose() * rec.matrix();
+    assert_relative_eq!(m, Matrix3f::identity(), epsilon = 1e-8);
+    assert_relative_eq!(rec.volume(), 1.0 / lat.volume(), epsilon = 1e-8);
+    assert_relative_eq!(rec.reciprocal().matrix(), lat.matrix(), epsilon = 1e-8);
+    let m = lat.matrix().transpose() * lat.reciprocal_2pi().matrix();
+    assert_relative_eq!(m, Matrix3f::identity() * 2.0 * PI, epsilon = 1e-8);
+}
+
+#[test]
+fn test_g_vectors() {
+    use approx::*;
+
+    // reciprocal lattice vectors are of unit length
+    let a = 2.0 * PI;
+    let lat = Lattice::from_params(a, a, a, 90.0, 90.0, 90.0);
+    assert_eq!(lat.g_vectors(1.01).len(), 7);
+    assert_eq!(lat.g_vectors(1.5).len(), 19);
+    let gs = lat.g_vectors(1.8);
+    assert_eq!(gs.len(), 27);
+    assert_eq!(gs[0].hkl, [0, 0, 0]);
+    assert_relative_eq!(gs[26].norm(), 3f64.sqrt(), epsilon = 1e-8);
+
+    // compare with a brute-force search in a large box
+    let lat = Lattice::from_params(3.0, 4.0, 5.0, 60.0, 110.0, 70.0);
+    let g_max = 6.0;
+    let rec = lat.reciprocal_2pi();
+    let n = iproduct!(-20..=20, -20..=20, -20..=20)
+        .filter(|&(h, k, l)| {
+            (rec.matrix() * Vector3f::from([h as f64, k as f64, l as f64])).norm() < g_max
+        })
+        .count();
+    assert_eq!(lat.g_vectors(g_max).len(), n);
+    // 1 Ry = 13.6057 eV: |G|^2 < ecut in Rydberg atomic units
+    let gs = lat.g_vectors_within_ecut(20.0 * 13.605693);
+    let g_max = 20f64.sqrt() / 0.52917721;
+    assert_eq!(gs.len(), lat.g_vectors(g_max).len());
+
+    // FFT grid
+    assert_eq!(next_fft_size(11), 12);
+    assert_eq!(next_fft_size(13), 14);
+    assert_eq!(next_fft_size(31), 32);
+    let lat = Lattice::from_params(10.0, 10.0, 10.0, 90.0, 90.0, 90.0);
+    assert_eq!(lat.fft_grid_size(2.0), [7, 7, 7]);
+    let grid = lat.fft_grid_size(5.0);
+    let nmax = lat.g_vectors(5.0).iter().map(|g| g.hkl[0]).max().unwrap() as usize;
+    assert!(grid[0] > 2 * nmax);
+
+    // the same fcc lattice in right- and left-handed bases
+    let right = Lattice::new([[0.0, 2.0, 2.0], [2.0, 0.0, 2.0], [2.0, 2.0, 0.0]]);
+    let left = Lattice::new([[0.0, 2.0, 2.0], [2.0, 2.0, 0.0], [2.0, 0.0, 2.0]]);
+    assert!(left.volume() < 0.0);
+    assert_eq!(left.g_vectors(5.0).len(), 27);
+    assert_eq!(left.g_vectors(5.0).len(), right.g_vectors(5.0).len());
+    assert_eq!(left.fft_grid_size(5.0), right.fft_grid_size(5.0));
+}
+// 5a2e80b4 ends here
@@ -0,0 +1,160 @@
+// [[file:../lattice.note::1f6a8c3b][1f6a8c3b]]
+use crate::utils::*;
+use crate::Lattice;
+
+use vecfx::*;
+// 1f6a8c3b ends here
+
+// [[file:../lattice.note::7e02d4a9][7e02d4a9]]
+// Niggli parameters: A, B, C, ξ, η, ζ
+fn niggli_params(mat: &Matrix3f) -> [f64; 6] {
+    let g = mat.transpose() * mat;
+    [
+        g[(0, 0)],
+        g[(1, 1)],
+        g[(2, 2)],
+        2.0 * g[(1, 2)],
+        2.0 * g[(0, 2)],
+        2.0 * g[(0, 1)],
+    ]
+}
+
+// sign of x with tolerance `eps`: -1, 0 or 1
+fn sign_eps(x: f64, eps: f64) -> isize {
+    if x > eps {
+        1
+    } else if x < -eps {
+        -1
+    } else {
+        0
+    }
+}
+
+fn sign(x: f64) -> isize {
+    if x < 0.0 {
+        -1
+    } else {
+        1
+    }
+}
+
+/// Reduce lattice `mat` (column vectors) using Krivy-Gruber algorithm.
+/// Returns the integer transformation `t` in column convention: `reduced
+/// = mat * t`.
+///
+/// Reference
+/// ---------
+/// - Krivy, I.; Gruber, B. Acta Cryst. 1976, A32, 297-298.
+/// - Grosse-Kunstleve, R. W.; Sauter, N. K.; Adams, P. D. Acta Cryst. 2004,
+///   A60, 1-6.
+fn niggli_reduce_matrix(mat: &Matrix3f) -> [[isize; 3]; 3] {
+    let eps = 1e-5 * get_cell_volume(*mat).abs().powf(2.0 / 3.0);
+    let lt = |x: f64, y: f64| x < y - eps;
+    let gt = |x: f64, y: f64| lt(y, x);
+    let eq = |x: f64, y: f64| !(lt(x, y) || gt(x, y));
+
+    let mut t = [[1, 0, 0], [0, 1, 0], [0, 0, 1]];
+    let mut cell = *mat;
+    let mut apply = |cell: &mut Matrix3f, m: [[isize; 3]; 3]| {
+        *cell *= imatrix_to_matrix3f(m);
+        t = imatrix_mul(t, m);
+    };
+
+    for _ in 0..1000 {
+        let [a, b, c, xi, eta, zeta] = niggli_params(&cell);
+        // N1
+        if gt(a, b) || (eq(a, b) && gt(xi.abs(), eta.abs())) {
+            apply(&mut cell, [[0, -1, 0], [-1, 0, 0], [0, 0, -1]]);
+            continue;
+        }
+        // N2
+        if gt(b, c) || (eq(b, c) && gt(eta.abs(), zeta.abs())) {
+            apply(&mut cell, [[-1, 0, 0], [0, 0, -1], [0, -1, 0]]);
+            continue;
+        }
+        // N3, N4
+        let [l, m, n] = [xi, eta, zeta].map(|x| sign_eps(x, eps));
+        if l * m * n == 1 {
+            let [i, j, k] = [l, m, n].map(|s| if s == -1 { -1 } else { 1 });
+            if [i, j, k] != [1, 1, 1] {
+                apply(&mut cell, [[i, 0, 0], [0, j, 0], [0, 0, k]]);
+            }
+        } else {
+            let mut ijk = [l, m, n].map(|s| if s == 1 { -1 } else { 1 });
+            if ijk.iter().product::<isize>() < 0 {
+                let p = [l, m, n].iter().position(|&s| s == 0).expect("niggli: N4");
+                ijk[p] = -1;
+            }
+            if ijk != [1, 1, 1] {
+                let [i, j, k] = ijk;
+                apply(&mut cell, [[i, 0, 0], [0, j, 0], [0, 0, k]]);
+            }
+        }
+        let [a, b, _, xi, eta, zeta] = niggli_params(&cell);
+        // N5
+        if gt(xi.abs(), b) || (eq(xi, b) && lt(2.0 * eta, zeta)) || (eq(xi, -b) && lt(zeta, 0.0)) {
+            apply(&mut cell, [[1, 0, 0], [0, 1, -sign(xi)], [0, 0, 1]]);
+            continue;
+        }
+        // N6
+        if gt(eta.abs(), a) || (eq(eta, a) && lt(2.0 * xi, zeta)) || (eq(eta, -a) && lt(zeta, 0.0))
+        {
+            apply(&mut cell, [[1, 0, -sign(eta)], [0, 1, 0], [0, 0, 1]]);
+            continue;
+        }
+        // N7
+        if gt(zeta.abs(), a) || (eq(zeta, a) && lt(2.0 * xi, eta)) || (eq(zeta, -a) && lt(eta, 0.0))
+        {
+            apply(&mut cell, [[1, -sign(zeta), 0], [0, 1, 0], [0, 0, 1]]);
+            continue;
+        }
+        // N8
+        let s = xi + eta + zeta + a + b;
+        if lt(s, 0.0) || (eq(s, 0.0) && gt(2.0 * (a + eta) + zeta, 0.0)) {
+            apply(&mut cell, [[1, 0, 1], [0, 1, 1], [0, 0, 1]]);
+            continue;
+        }
+        return t;
+    }
+    panic!("Niggli reduction failed to converge");
+}
+
+impl Lattice {
+    /// Return the Niggli reduced lattice and the integer transformation
+    /// matrix `m` in the convention of [`Lattice::supercell`], i.e. the
+    /// reduced lattice is `self.supercell(m)`.
+    pub fn niggli_reduce(&self) -> (Lattice, [[isize; 3]; 3]) {
+        let m = imatrix_transpose(niggli_reduce_matrix(&self.matrix()));
+        (self.supercell(m), m)
+    }
+}
+// 7e02d4a9 ends here
+
+// [[file:../lattice.note::c2b85d1e][c2b85d1e]]
+#[test]
+fn test_niggli_reduce() {
+    use approx::*;
+
+    // fcc lattice given by a skewed basis
+    let lat = Lattice::new([[0.0, 2.0, 2.0], [2.0, 0.0, 2.0], [2.0, 2.0, 0.0]]);
+    let skewed = lat.supercell([[1, 0, 0], [3, 1, 0], [-2, 5, 1]]);
+    let (red, m) = skewed.niggli_reduce();
+    assert_eq!(imatrix_det(m), 1);
+    for x in red.lengths() {
+        assert_relative_eq!(x, 8f64.sqrt(), epsilon = 1e-6);
+    }
+    for x in red.angles() {
+        assert_relative_eq!(x, 60.0, epsilon = 1e-6);
+    }
+    assert_relative_eq!(red.volume(), lat.volume(), epsilon = 1e-6);
+
+    // bcc lattice: a = b = c, all angles are 109.47
+    let lat = Lattice::new([[-1.5, 1.5, 1.5], [1.5, -1.5, 1.5], [1.5, 1.5, -1.5]]);
+    let (red, _) = lat
+        .supercell([[1, 1, 0], [0, 1, 0], [0, 1, 1]])
+        .niggli_reduce();
+    for x in red.angles() {
+        assert_relative_eq!(x, 109.4712, epsilon = 1e-4);
+    }
+}
+// c2b85d1e ends here
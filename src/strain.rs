@@ -0,0 +1,375 @@
+// [[file:../lattice.note::c3e81a5f][c3e81a5f]]
+use crate::utils::*;
+use crate::Lattice;
+
+use vecfx::nalgebra::Rotation3;
+use vecfx::*;
+// c3e81a5f ends here
+
+// [[file:../lattice.note::7f2b9d04][7f2b9d04]]
+/// Measures of finite strain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrainMeasure {
+    /// Infinitesimal strain: F = I + ε
+    Small,
+    /// Green-Lagrange strain: E = (FᵀF - I)/2
+    Lagrangian,
+    /// Euler-Almansi strain: e = (I - (FFᵀ)⁻¹)/2
+    Eulerian,
+}
+
+/// A symmetric strain tensor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Strain {
+    matrix: Matrix3f,
+}
+
+impl Strain {
+    /// Construct from 3x3 strain tensor. The tensor is symmetrized. Nested
+    /// arrays are converted by `Into<Matrix3f>`, i.e. column by column as
+    /// in [`Lattice::from_matrix`], which makes no difference for symmetric
+    /// tensors.
+    pub fn new<T: Into<Matrix3f>>(matrix: T) -> Self {
+        let m = matrix.into();
+        Self {
+            matrix: 0.5 * (m + m.transpose()),
+        }
+    }
+
+    /// Construct from strain in Voigt notation: [ε11, ε22, ε33, γ23, γ13,
+    /// γ12], using engineering shear strains γij = 2εij.
+    pub fn from_voigt(v: [f64; 6]) -> Self {
+        let [xx, yy, zz, yz, xz, xy] = v;
+        let matrix = Matrix3f::new(
+            xx,
+            0.5 * xy,
+            0.5 * xz,
+            0.5 * xy,
+            yy,
+            0.5 * yz,
+            0.5 * xz,
+            0.5 * yz,
+            zz,
+        );
+        Self { matrix }
+    }
+
+    /// Return strain in Voigt notation with engineering shear strains.
+    pub fn to_voigt(&self) -> [f64; 6] {
+        let m = &self.matrix;
+        [
+            m[(0, 0)],
+            m[(1, 1)],
+            m[(2, 2)],
+            2.0 * m[(1, 2)],
+            2.0 * m[(0, 2)],
+            2.0 * m[(0, 1)],
+        ]
+    }
+
+    /// The strain tensor as 3x3 matrix.
+    pub fn matrix(&self) -> Matrix3f {
+        self.matrix
+    }
+
+    /// Return the deformation gradient without rigid rotation (symmetric
+    /// stretch) that produces this strain in `measure`.
+    pub fn deformation_gradient(&self, measure: StrainMeasure) -> Matrix3f {
+        let i = Matrix3f::identity();
+        match measure {
+            StrainMeasure::Small => i + self.matrix,
+            // F = sqrt(I + 2E)
+            StrainMeasure::Lagrangian => {
+                let c = i + 2.0 * self.matrix;
+                sym_matrix_map(c, |x| {
+                    assert!(x > 0.0, "invalid Lagrangian strain: {:?}", self.matrix);
+                    x.sqrt()
+                })
+            }
+            // F = (I - 2e)^(-1/2)
+            StrainMeasure::Eulerian => {
+                let b_inv = i - 2.0 * self.matrix;
+                sym_matrix_map(b_inv, |x| {
+                    assert!(x > 0.0, "invalid Eulerian strain: {:?}", self.matrix);
+                    1.0 / x.sqrt()
+                })
+            }
+        }
+    }
+
+    /// Return the strain in `measure` of deformation gradient `f`. The
+    /// small strain is the symmetric part of `f - I`.
+    pub fn from_deformation_gradient(f: Matrix3f, measure: StrainMeasure) -> Self {
+        let i = Matrix3f::identity();
+        let matrix = match measure {
+            StrainMeasure::Small => 0.5 * (f + f.transpose()) - i,
+            StrainMeasure::Lagrangian => 0.5 * (f.transpose() * f - i),
+            StrainMeasure::Eulerian => {
+                let b = f * f.transpose();
+                0.5 * (i - get_inv_matrix(&b))
+            }
+        };
+        Self::new(matrix)
+    }
+}
+
+/// Polar decomposition of deformation gradient `f = R U` into rigid
+/// rotation `R` and symmetric right stretch tensor `U`.
+pub fn polar_decomposition(f: Matrix3f) -> (Matrix3f, Matrix3f) {
+    assert!(f.determinant() > 0.0, "invalid deformation gradient: {f}");
+    let svd = f.svd(true, true);
+    let w = svd.u.unwrap();
+    let v_t = svd.v_t.unwrap();
+    let r = w * v_t;
+    let u = v_t.transpose() * Matrix3f::from_diagonal(&svd.singular_values) * v_t;
+    (r, u)
+}
+
+impl From<Matrix3f> for Strain {
+    fn from(m: Matrix3f) -> Self {
+        Self::new(m)
+    }
+}
+
+impl From<[[f64; 3]; 3]> for Strain {
+    fn from(m: [[f64; 3]; 3]) -> Self {
+        Self::new(m)
+    }
+}
+
+impl From<[f64; 6]> for Strain {
+    fn from(v: [f64; 6]) -> Self {
+        Self::from_voigt(v)
+    }
+}
+
+impl Lattice {
+    /// Deform the lattice by deformation gradient `f` in Cartesian space,
+    /// i.e. each lattice vector `v` becomes `f v`. The cell origin is kept
+    /// unchanged. Nested arrays are converted by `Into<Matrix3f>`, i.e.
+    /// column by column as in [`Lattice::from_matrix`]; use
+    /// `Matrix3f::new` to give `f` row by row.
+    pub fn deform<T: Into<Matrix3f>>(&mut self, f: T) {
+        let f = f.into();
+        assert!(f.determinant() > 0.0, "invalid deformation gradient: {f}");
+        self.matrix = f * self.matrix;
+        self.inv_matrix = get_inv_matrix(&self.matrix);
+    }
+
+    /// Apply `strain` in `measure` to the lattice without rigid rotation.
+    /// If `preserve_volume` is true, the deformation is rescaled
+    /// isotropically to keep the cell volume.
+    pub fn apply_strain<T: Into<Strain>>(
+        &mut self,
+        strain: T,
+        measure: StrainMeasure,
+        preserve_volume: bool,
+    ) {
+        let mut f = strain.into().deformation_gradient(measure);
+        if preserve_volume {
+            let det = f.determinant();
+            assert!(det > 0.0, "invalid strain: {f}");
+            f /= det.cbrt();
+        }
+        self.deform(f);
+    }
+
+    /// Return the deformation gradient `F` that maps the lattice vectors of
+    /// `self` onto those of `deformed`, i.e. `B = F A` with `A` and `B` the
+    /// lattice matrices. The lattice vectors are matched in order, so both
+    /// lattices should be given in corresponding bases.
+    pub fn deformation_gradient(&self, deformed: &Lattice) -> Matrix3f {
+        deformed.matrix * self.inv_matrix
+    }
+
+    /// Return the strain from `self` to `deformed` in `measure`. Use
+    /// `StrainMeasure::Small` and [`Strain::to_voigt`] for engineering
+    /// strains.
+    pub fn strain_to(&self, deformed: &Lattice, measure: StrainMeasure) -> Strain {
+        Strain::from_deformation_gradient(self.deformation_gradient(deformed), measure)
+    }
+
+    /// Interpolate between `self` (`t = 0`) and `other` (`t = 1`). The
+    /// deformation gradient `F = R U` between them is split by polar
+    /// decomposition, and the rotation `R` and the logarithm of stretch `U`
+    /// are interpolated separately, so the volume stays positive along the
+    /// path and rigid rotations keep the cell shape. The origin is
+    /// interpolated linearly.
+    pub fn interpolate(&self, other: &Lattice, t: f64) -> Lattice {
+        let (r, u) = polar_decomposition(self.deformation_gradient(other));
+        let r = Rotation3::from_matrix_unchecked(r).powf(t);
+        let u = sym_matrix_map(u, |x| x.powf(t));
+        let mut lattice = *self;
+        lattice.deform(r * u);
+        lattice.origin = self.origin + (other.origin - self.origin) * t;
+        lattice
+    }
+
+    /// Return `n` evenly spaced images between `self` and `other` using
+    /// `interpolate`, excluding both end points.
+    pub fn interpolate_images(&self, other: &Lattice, n: usize) -> Vec<Lattice> {
+        (1..=n)
+            .map(|i| self.interpolate(other, i as f64 / (n + 1) as f64))
+            .collect()
+    }
+}
+// 7f2b9d04 ends here
+
+// [[file:../lattice.note::e0a4c7b6][e0a4c7b6]]
+#[test]
+fn test_apply_strain() {
+    use vecfx::approx::*;
+
+    let lat0 = Lattice::from_params(3.0, 4.0, 5.0, 90.0, 100.0, 90.0);
+    let a0 = lat0.matrix();
+
+    // Voigt notation uses engineering shear strains
+    let voigt = [0.01, -0.02, 0.005, 0.02, -0.01, 0.03];
+    let strain = Strain::from_voigt(voigt);
+    assert_eq!(strain.matrix()[(0, 1)], 0.015);
+    assert_relative_eq!(
+        strain.to_voigt().as_slice(),
+        voigt.as_slice(),
+        epsilon = 1e-12
+    );
+
+    // small strain
+    let mut lat = lat0;
+    lat.apply_strain(voigt, StrainMeasure::Small, false);
+    let f = lat.matrix() * a0.try_inverse().unwrap();
+    assert_relative_eq!(f, Matrix3f::identity() + strain.matrix(), epsilon = 1e-12);
+
+    // Lagrangian strain: E = (FᵀF - I)/2
+    let mut lat = lat0;
+    lat.apply_strain(strain, StrainMeasure::Lagrangian, false);
+    let f = lat.matrix() * a0.try_inverse().unwrap();
+    assert_relative_eq!(f, f.transpose(), epsilon = 1e-12);
+    let e = 0.5 * (f.transpose() * f - Matrix3f::identity());
+    assert_relative_eq!(e, strain.matrix(), epsilon = 1e-12);
+
+    // keep volume
+    let lat0 = Lattice::from_params(3.0, 4.0, 5.0, 90.0, 90.0, 90.0);
+    let mut lat = lat0;
+    lat.apply_strain(
+        [[0.1, 0.0, 0.0], [0.0, 0.0, 0.0], [0.0, 0.0, 0.0]],
+        StrainMeasure::Small,
+        true,
+    );
+    assert_relative_eq!(lat.volume(), lat0.volume(), epsilon = 1e-8);
+    let [a, b, c] = lat.lengths();
+    assert_relative_eq!(a / b, 1.1 * 3.0 / 4.0, epsilon = 1e-8);
+    assert_relative_eq!(c / b, 5.0 / 4.0, epsilon = 1e-8);
+
+    // simple shear changes the angle only
+    let mut lat = Lattice::from_params(3.0, 3.0, 3.0, 90.0, 90.0, 90.0);
+    lat.deform(Matrix3f::new(1.0, 0.0, 0.5, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0));
+    assert_relative_eq!(lat.volume(), 27.0, epsilon = 1e-8);
+    assert_relative_eq!(lat.vector_c(), Vector3f::new(1.5, 0.0, 3.0), epsilon = 1e-8);
+
+    // nested arrays are read column by column
+    let mut lat1 = Lattice::from_params(3.0, 3.0, 3.0, 90.0, 90.0, 90.0);
+    lat1.deform([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.5, 0.0, 1.0]]);
+    assert_relative_eq!(lat1.matrix(), lat.matrix(), epsilon = 1e-12);
+}
+
+#[test]
+fn test_strain_between_lattices() {
+    use vecfx::approx::*;
+
+    let lat0 = Lattice::from_params(3.0, 4.0, 5.0, 80.0, 100.0, 70.0);
+    let strain = Strain::from_voigt([0.01, -0.02, 0.005, 0.02, -0.01, 0.03]);
+    let u = strain.deformation_gradient(StrainMeasure::Lagrangian);
+    // rotation by 0.3 rad about axis (1, 2, 3)
+    let axis = Vector3f::new(1.0, 2.0, 3.0).normalize();
+    let r = *Rotation3::new(axis * 0.3).matrix();
+    let mut lat = lat0;
+    lat.deform(r * u);
+
+    let f = lat0.deformation_gradient(&lat);
+    assert_relative_eq!(f, r * u, epsilon = 1e-12);
+    let (r1, u1) = polar_decomposition(f);
+    assert_relative_eq!(r1, r, epsilon = 1e-10);
+    assert_relative_eq!(u1, u, epsilon = 1e-10);
+
+    // Lagrangian strain is free of rigid rotation
+    let e = lat0.strain_to(&lat, StrainMeasure::Lagrangian);
+    assert_relative_eq!(e.matrix(), strain.matrix(), epsilon = 1e-12);
+
+    // round trip of Eulerian strain
+    let e = Strain::from_voigt([0.02, 0.0, -0.01, 0.0, 0.04, 0.0]);
+    let mut lat = lat0;
+    lat.apply_strain(e, StrainMeasure::Eulerian, false);
+    let e1 = lat0.strain_to(&lat, StrainMeasure::Eulerian);
+    assert_relative_eq!(e1.matrix(), e.matrix(), epsilon = 1e-12);
+
+    // all measures agree to first order
+    let small = Strain::from_voigt([1e-6, 2e-6, -1e-6, 3e-6, 0.0, -2e-6]);
+    let mut lat = lat0;
+    lat.apply_strain(small, StrainMeasure::Small, false);
+    for measure in [
+        StrainMeasure::Small,
+        StrainMeasure::Lagrangian,
+        StrainMeasure::Eulerian,
+    ] {
+        let e = lat0.strain_to(&lat, measure).to_voigt();
+        assert_relative_eq!(e.as_slice(), small.to_voigt().as_slice(), epsilon = 1e-10);
+    }
+}
+
+#[test]
+fn test_interpolate_lattices() {
+    use vecfx::approx::*;
+
+    let lat0 = Lattice::from_params(3.0, 4.0, 5.0, 80.0, 100.0, 70.0);
+    let axis = Vector3f::new(1.0, 2.0, 3.0).normalize();
+
+    // rigid rotation keeps the cell shape
+    let mut lat1 = lat0;
+    lat1.rotate_about(axis, 60.0);
+    let mid = lat0.interpolate(&lat1, 0.5);
+    assert_relative_eq!(
+        mid.lengths().as_slice(),
+        lat0.lengths().as_slice(),
+        epsilon = 1e-10
+    );
+    assert_relative_eq!(
+        mid.angles().as_slice(),
+        lat0.angles().as_slice(),
+        epsilon = 1e-10
+    );
+    let mut expected = lat0;
+    expected.rotate_about(axis, 30.0);
+    assert_relative_eq!(mid.matrix(), expected.matrix(), epsilon = 1e-10);
+
+    // isotropic scaling is interpolated geometrically
+    let mut lat1 = lat0;
+    lat1.scale_by(4.0);
+    lat1.set_origin([2.0, 0.0, 0.0]);
+    let mid = lat0.interpolate(&lat1, 0.5);
+    assert_relative_eq!(mid.volume(), 8.0 * lat0.volume(), epsilon = 1e-8);
+    assert_relative_eq!(mid.origin(), Vector3f::new(1.0, 0.0, 0.0), epsilon = 1e-10);
+
+    // large deformation: positive volume and exact end points
+    let mut lat1 = lat0;
+    lat1.deform(Matrix3f::new(2.0, 1.5, 0.0, -0.5, 0.5, 0.3, 0.2, 0.0, 1.5));
+    lat1.rotate_about(axis, 120.0);
+    assert_relative_eq!(
+        lat0.interpolate(&lat1, 0.0).matrix(),
+        lat0.matrix(),
+        epsilon = 1e-10
+    );
+    assert_relative_eq!(
+        lat0.interpolate(&lat1, 1.0).matrix(),
+        lat1.matrix(),
+        epsilon = 1e-10
+    );
+    let images = lat0.interpolate_images(&lat1, 9);
+    assert_eq!(images.len(), 9);
+    let volumes: Vec<_> = images.iter().map(|l| l.volume()).collect();
+    assert!(volumes.iter().all(|&v| v > 0.0));
+    // det(U^t) changes monotonically
+    assert!(volumes
+        .windows(2)
+        .all(|w| (w[1] - w[0]) * (lat1.volume() - lat0.volume()) > 0.0));
+}
+// e0a4c7b6 ends here
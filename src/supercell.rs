@@ -1,9 +1,10 @@
 // base
 
 // [[file:~/Workspace/Programming/gchemol-rs/lattice/lattice.note::*base][base:1]]
+use crate::utils::*;
 use crate::Lattice;
 use gchemol_gut::itertools::*;
-use vecfx::Vector3f;
+use vecfx::*;
 
 impl Lattice {
     /// Create a supercell along three cell directions.
@@ -15,6 +16,17 @@ impl Lattice {
     ) -> impl Iterator<Item = Vector3f> {
         iproduct!(ra, rb, rc).map(|(i, j, k)| Vector3f::from([i as f64, j as f64, k as f64]))
     }
+
+    /// Create a supercell lattice from integer transformation matrix `m`.
+    /// Row `i` of `m` holds the coefficients of new lattice vector `i` in
+    /// terms of lattice vectors `a`, `b` and `c`. The origin is kept
+    /// unchanged.
+    pub fn supercell(&self, m: [[isize; 3]; 3]) -> Lattice {
+        assert_ne!(imatrix_det(m), 0, "singular supercell matrix: {m:?}");
+        let mut lat = Lattice::from_matrix(get_supercell_matrix(&self.matrix(), m));
+        lat.set_origin(self.origin());
+        lat
+    }
 }
 
 // #[cfg(feature = "adhoc")]
@@ -36,6 +48,68 @@ impl Lattice {
 // }
 // base:1 ends here
 
+// [[file:../lattice.note::9d1e7a40][9d1e7a40]]
+impl Lattice {
+    /// Search for the supercell closest to a cube with all perpendicular
+    /// widths no less than `min_width`, using no more than `max_cells`
+    /// primitive cells. Returns the integer transformation matrix (see
+    /// [`Lattice::supercell`]) and the resulting supercell lattice, or
+    /// `None` if no candidate satisfies the width constraint.
+    ///
+    /// For each supercell size `n`, the candidates are integer matrices
+    /// around the ideal transformation to a cube of volume `n * V`. The
+    /// cubicity deviation is measured on the normalized metric tensor
+    /// `|G / V^(2/3) - I|`, which is independent of cell orientation. Ties
+    /// are broken in favor of the smaller supercell.
+    pub fn optimal_supercell(
+        &self,
+        min_width: f64,
+        max_cells: usize,
+    ) -> Option<([[isize; 3]; 3], Lattice)> {
+        let volume = self.volume().abs();
+        let mut best: Option<(f64, [[isize; 3]; 3])> = None;
+        for n in 1..=max_cells as isize {
+            // ideal transformation matrix for a cube of volume n*V, keeping
+            // the handedness of the lattice
+            let l = (n as f64 * volume).cbrt();
+            let mut p0 = (self.inv_matrix() * l).transpose();
+            if self.volume() < 0.0 {
+                p0.row_mut(2).neg_mut();
+            }
+            let offsets = std::iter::repeat_n(-1..=1, 9).multi_cartesian_product();
+            for d in offsets {
+                let m: [[isize; 3]; 3] = std::array::from_fn(|i| {
+                    std::array::from_fn(|j| p0[(i, j)].round() as isize + d[3 * i + j])
+                });
+                if imatrix_det(m) != n {
+                    continue;
+                }
+                let mat = get_supercell_matrix(&self.matrix(), m);
+                if get_cell_widths(mat).iter().any(|&w| w < min_width) {
+                    continue;
+                }
+                let score = get_cubic_deviation(mat);
+                // keep the first one found in case of tie, i.e. the smaller
+                // supercell
+                if best.is_none_or(|(s, _)| score < s - 1e-8) {
+                    best = Some((score, m));
+                }
+            }
+        }
+
+        let (_, m) = best?;
+        Some((m, self.supercell(m)))
+    }
+}
+
+// deviation from a perfect cube in terms of normalized metric tensor
+fn get_cubic_deviation(mat: Matrix3f) -> f64 {
+    let v = get_cell_volume(mat).abs();
+    let g = mat.transpose() * mat / v.powf(2.0 / 3.0);
+    (g - Matrix3f::identity()).norm()
+}
+// 9d1e7a40 ends here
+
 // test
 
 // [[file:~/Workspace/Programming/gchemol-rs/lattice/lattice.note::*test][test:1]]
@@ -53,4 +127,40 @@ fn test_supercell() {
     let cell_images = lattice.replicate(-1..=1, -1..=1, -1..=1);
     assert_eq!(cell_images.count(), 27);
 }
+
+#[test]
+fn test_optimal_supercell() {
+    use approx::*;
+
+    // fcc primitive cell: the conventional cubic cell contains 4 primitive cells
+    let lat = Lattice::new([[0.0, 2.0, 2.0], [2.0, 0.0, 2.0], [2.0, 2.0, 0.0]]);
+    let (m, sc) = lat.optimal_supercell(3.5, 4).unwrap();
+    assert_eq!(imatrix_det(m), 4);
+    for x in sc.lengths() {
+        assert_relative_eq!(x, 4.0, epsilon = 1e-6);
+    }
+    for x in sc.angles() {
+        assert_relative_eq!(x, 90.0, epsilon = 1e-6);
+    }
+    assert_relative_eq!(sc.volume(), lat.volume() * 4.0, epsilon = 1e-6);
+
+    // the widths constraint cannot be satisfied
+    assert!(lat.optimal_supercell(10.0, 4).is_none());
+
+    // left-handed fcc primitive cell
+    let lat = Lattice::new([[0.0, 2.0, 2.0], [2.0, 2.0, 0.0], [2.0, 0.0, 2.0]]);
+    let (m, sc) = lat.optimal_supercell(3.5, 4).unwrap();
+    assert_eq!(imatrix_det(m), 4);
+    for x in sc.lengths() {
+        assert_relative_eq!(x, 4.0, epsilon = 1e-6);
+    }
+    assert_relative_eq!(sc.volume(), lat.volume() * 4.0, epsilon = 1e-6);
+
+    // supercell keeps lattice origin
+    let mut lat = Lattice::default();
+    lat.set_origin([1.0, 2.0, 3.0]);
+    let sc = lat.supercell([[2, 0, 0], [0, 1, 0], [0, 0, 1]]);
+    assert_eq!(sc.origin(), lat.origin());
+    assert_eq!(sc.lengths(), [2.0, 1.0, 1.0]);
+}
 // test:1 ends here
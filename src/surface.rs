@@ -0,0 +1,231 @@
+// [[file:../lattice.note::b6f2093e][b6f2093e]]
+use crate::utils::*;
+use crate::Lattice;
+
+use vecfx::*;
+// b6f2093e ends here
+
+// [[file:../lattice.note::2a8de51c][2a8de51c]]
+/// A surface slab built from a bulk lattice, as returned by
+/// [`Lattice::surface`].
+///
+/// The first two lattice vectors span the surface plane and are periodic;
+/// the third one, indexed by [`Slab::NON_PERIODIC_AXIS`], points out of the
+/// surface and includes the vacuum region.
+#[derive(Debug, Clone, Copy)]
+pub struct Slab {
+    lattice: Lattice,
+    transform: [[isize; 3]; 3],
+    normal: Vector3f,
+    thickness: f64,
+    vacuum: f64,
+}
+
+impl Slab {
+    /// The index of the non-periodic lattice vector, i.e. `c`.
+    pub const NON_PERIODIC_AXIS: usize = 2;
+
+    /// The slab lattice including the vacuum.
+    pub fn lattice(&self) -> Lattice {
+        self.lattice
+    }
+
+    /// The integer transformation matrix from the bulk lattice to the slab
+    /// without vacuum, in the convention of [`Lattice::supercell`].
+    pub fn transform(&self) -> [[isize; 3]; 3] {
+        self.transform
+    }
+
+    /// The unit normal vector of the surface plane in Cartesian
+    /// coordinates, pointing towards the vacuum.
+    pub fn normal(&self) -> Vector3f {
+        self.normal
+    }
+
+    /// The thickness of the bulk region along the surface normal.
+    pub fn thickness(&self) -> f64 {
+        self.thickness
+    }
+
+    /// The thickness of the vacuum region along the surface normal.
+    pub fn vacuum(&self) -> f64 {
+        self.vacuum
+    }
+
+    /// Map fractional coordinates in the bulk lattice to fractional
+    /// coordinates in the slab lattice. Points inside the bulk region have
+    /// the third coordinate in `[0, thickness / (thickness + vacuum))`
+    /// after wrapping.
+    pub fn to_slab_frac<T: Into<Vector3f>>(&self, f: T) -> Vector3f {
+        let p = imatrix_to_matrix3f(self.transform).transpose();
+        let mut fs = get_inv_matrix(&p) * f.into();
+        fs.z *= self.thickness / (self.thickness + self.vacuum);
+        fs
+    }
+}
+
+// Reduce two in-plane lattice vectors to the shortest and most orthogonal
+// pair (Lagrange-Gauss reduction). `u` and `v` are integer coefficients
+// of lattice vectors.
+pub(crate) fn gauss_reduce(
+    lat: &Lattice,
+    mut u: [isize; 3],
+    mut v: [isize; 3],
+) -> ([isize; 3], [isize; 3]) {
+    let cart = |x: [isize; 3]| lat.matrix() * Vector3f::from(x.map(|i| i as f64));
+    loop {
+        if cart(v).norm_squared() < cart(u).norm_squared() {
+            std::mem::swap(&mut u, &mut v);
+        }
+        let mu = cart(u).dot(&cart(v)) / cart(u).norm_squared();
+        if mu.abs() <= 0.5 + 1e-8 {
+            break;
+        }
+        let mu = mu.round() as isize;
+        v = [0, 1, 2].map(|i| v[i] - mu * u[i]);
+    }
+    (u, v)
+}
+
+impl Lattice {
+    /// Build a surface slab for the (`h`, `k`, `l`) plane, with `layers`
+    /// layers of bulk material and `vacuum` Angstrom of vacuum along the
+    /// surface normal.
+    ///
+    /// The in-plane vectors are chosen as the shortest and most orthogonal
+    /// lattice vectors in the plane. The out-of-plane vector spans exactly
+    /// one layer and is chosen as close to the surface normal as possible.
+    /// The vacuum is added by stretching the out-of-plane vector, so that
+    /// the fractional coordinates of the bulk region are only rescaled
+    /// along `c` (see [`Slab::to_slab_frac`]).
+    pub fn surface(&self, h: isize, k: isize, l: isize, layers: usize, vacuum: f64) -> Slab {
+        assert!(
+            [h, k, l] != [0, 0, 0],
+            "invalid Miller indices: ({h} {k} {l})"
+        );
+        assert!(layers > 0, "invalid number of layers: {layers}");
+        assert!(vacuum >= 0.0, "invalid vacuum size: {vacuum}");
+
+        let g = gcd(gcd(h, k), l);
+        let [h, k, l] = [h / g, k / g, l / g];
+        // find a basis of the plane and a vector spanning one layer
+        let (u, v, w) = if h == 0 && k == 0 {
+            ([1, 0, 0], [0, 1, 0], [0, 0, l])
+        } else if k == 0 && l == 0 {
+            ([0, 1, 0], [0, 0, 1], [h, 0, 0])
+        } else {
+            let (p, q) = ext_gcd(k, l);
+            let s = p * k + q * l;
+            let (x, y) = ext_gcd(s, h);
+            let u = [s, -p * h, -q * h];
+            let v = [0, l / s, -k / s];
+            (u, v, [y, x * p, x * q])
+        };
+        debug_assert_eq!(h * w[0] + k * w[1] + l * w[2], 1);
+
+        let (mut u, mut v) = gauss_reduce(self, u, v);
+        let cart = |x: [isize; 3]| self.matrix() * Vector3f::from(x.map(|i| i as f64));
+        let normal = self.plane_normal(h, k, l);
+        if cart(u).cross(&cart(v)).dot(&normal) < 0.0 {
+            std::mem::swap(&mut u, &mut v);
+        }
+
+        // remove the in-plane component of w as much as possible
+        let plane = Matrix3f::from_columns(&[cart(u), cart(v), normal]);
+        let x = get_inv_matrix(&plane) * cart(w);
+        let w = (0..4)
+            .map(|i| {
+                let a = x[0].floor() as isize + i / 2;
+                let b = x[1].floor() as isize + i % 2;
+                [0, 1, 2].map(|j| w[j] - a * u[j] - b * v[j])
+            })
+            .min_by(|&x, &y| cart(x).norm_squared().total_cmp(&cart(y).norm_squared()))
+            .unwrap();
+
+        let n = layers as isize;
+        let transform = [u, v, w.map(|i| i * n)];
+        let mut lattice = self.supercell(transform);
+        let thickness = lattice.vector_c().dot(&normal);
+        lattice.scale_by_c((thickness + vacuum) / thickness);
+
+        Slab {
+            lattice,
+            transform,
+            normal,
+            thickness,
+            vacuum,
+        }
+    }
+}
+// 2a8de51c ends here
+
+// [[file:../lattice.note::d9047b31][d9047b31]]
+#[test]
+fn test_surface_slab() {
+    use approx::*;
+
+    // (111) surface of simple cubic lattice
+    let lat = Lattice::from_params(4.0, 4.0, 4.0, 90.0, 90.0, 90.0);
+    let slab = lat.surface(1, 1, 1, 3, 10.0);
+    let [a, b, _] = slab.lattice().lengths();
+    assert_relative_eq!(a, 4.0 * 2f64.sqrt(), epsilon = 1e-6);
+    assert_relative_eq!(b, 4.0 * 2f64.sqrt(), epsilon = 1e-6);
+    let [_, _, gamma] = slab.lattice().angles();
+    assert_relative_eq!((gamma - 90.0).abs(), 30.0, epsilon = 1e-6);
+    assert_relative_eq!(slab.thickness(), 3.0 * 4.0 / 3f64.sqrt(), epsilon = 1e-6);
+    assert_eq!(imatrix_det(slab.transform()), 3);
+    let c = slab.lattice().vector_c();
+    assert_relative_eq!(
+        c.dot(&slab.normal()),
+        slab.thickness() + 10.0,
+        epsilon = 1e-6
+    );
+    let area = slab
+        .lattice()
+        .vector_a()
+        .cross(&slab.lattice().vector_b())
+        .norm();
+    assert_relative_eq!(
+        slab.lattice().volume(),
+        area * (slab.thickness() + 10.0),
+        epsilon = 1e-6
+    );
+
+    // bulk fractional coordinates to slab fractional coordinates
+    let fs = slab.to_slab_frac([0.0, 0.0, 0.0]);
+    assert_relative_eq!(fs, Vector3f::zeros(), epsilon = 1e-6);
+    let [u, v, w] = slab
+        .transform()
+        .map(|x| Vector3f::from(x.map(|i| i as f64)));
+    assert_relative_eq!(
+        slab.to_slab_frac(u),
+        Vector3f::from([1.0, 0.0, 0.0]),
+        epsilon = 1e-6
+    );
+    assert_relative_eq!(
+        slab.to_slab_frac(v),
+        Vector3f::from([0.0, 1.0, 0.0]),
+        epsilon = 1e-6
+    );
+    let z = slab.thickness() / (slab.thickness() + slab.vacuum());
+    assert_relative_eq!(
+        slab.to_slab_frac(w),
+        Vector3f::from([0.0, 0.0, z]),
+        epsilon = 1e-6
+    );
+    let p = lat.to_cart([0.3, 0.2, 0.7]);
+    let fs = slab.to_slab_frac([0.3, 0.2, 0.7]);
+    assert_relative_eq!(slab.lattice().to_cart(fs), p, epsilon = 1e-6);
+
+    // negative indices and a skewed cell
+    let lat = Lattice::from_params(3.0, 4.0, 5.0, 80.0, 100.0, 70.0);
+    for (h, k, l) in [(0, 0, -1), (-2, 0, 0), (1, -1, 0), (2, 1, 0), (1, 2, 3)] {
+        let slab = lat.surface(h, k, l, 2, 5.0);
+        assert!(slab.lattice().volume() > 0.0);
+        let n = slab.normal();
+        assert_relative_eq!(slab.lattice().vector_a().dot(&n), 0.0, epsilon = 1e-6);
+        assert_relative_eq!(slab.lattice().vector_b().dot(&n), 0.0, epsilon = 1e-6);
+        assert_eq!(imatrix_det(slab.transform()), 2);
+    }
+}
+// d9047b31 ends here
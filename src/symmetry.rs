@@ -0,0 +1,148 @@
+// [[file:../lattice.note::4a7b9e03][4a7b9e03]]
+use crate::strain::polar_decomposition;
+use crate::utils::*;
+use crate::Lattice;
+
+use gchemol_gut::prelude::*;
+use vecfx::nalgebra::Rotation3;
+use vecfx::*;
+// 4a7b9e03 ends here
+
+// [[file:../lattice.note::e83d2f61][e83d2f61]]
+// all integer matrices with elements in {-1, 0, 1} and determinant ±1
+fn small_unimodular_matrices() -> impl Iterator<Item = [[isize; 3]; 3]> {
+    std::iter::repeat_n(-1..=1, 9)
+        .multi_cartesian_product()
+        .map(|x| std::array::from_fn(|i| std::array::from_fn(|j| x[3 * i + j])))
+        .filter(|&w| imatrix_det(w).abs() == 1)
+}
+
+impl Lattice {
+    /// Return the point-group operations of the lattice (the holohedry).
+    /// Each operation `w` is an integer matrix acting on fractional
+    /// coordinates as `f' = w f`; the corresponding Cartesian rotation is
+    /// `A w A^-1`, with `A` the lattice matrix. An operation is accepted if
+    /// its Cartesian rotation is orthogonal within `tol`.
+    pub fn point_group_operations(&self, tol: f64) -> Vec<[[isize; 3]; 3]> {
+        // In Niggli reduced basis, all elements of the operations are in
+        // {-1, 0, 1}.
+        let (reduced, m) = self.niggli_reduce();
+        let a = reduced.matrix();
+        let a_inv = reduced.inv_matrix();
+        let t = imatrix_transpose(m);
+        let t_inv = imatrix_inv_unimodular(t);
+        small_unimodular_matrices()
+            .filter(|&w| {
+                let r = a * imatrix_to_matrix3f(w) * a_inv;
+                let d = r.transpose() * r - Matrix3f::identity();
+                d.amax() < tol
+            })
+            // back to the original basis
+            .map(|w| imatrix_mul(imatrix_mul(t, w), t_inv))
+            .collect()
+    }
+
+    /// Test if `other` describes the same lattice as `self` up to the
+    /// choice of basis and a rigid rotation. If so, return the integer
+    /// matrix `m` in the convention of [`Lattice::supercell`] and the
+    /// rotation `r` such that `other` is `self.supercell(m)` rotated by
+    /// `r`. Lattice vector lengths are compared within relative tolerance
+    /// `tol`, and the cell origins are ignored.
+    pub fn is_equivalent(
+        &self,
+        other: &Lattice,
+        tol: f64,
+    ) -> Option<([[isize; 3]; 3], Rotation3<f64>)> {
+        let (ra, ma) = self.niggli_reduce();
+        let (rb, mb) = other.niggli_reduce();
+        let a = ra.matrix();
+        let b = rb.matrix();
+        let ga = a.transpose() * a;
+        let gb = b.transpose() * b;
+        let scale = gb.diagonal().max();
+
+        // B = R A W for reduced cells, with metric tensors Wᵀ Ga W = Gb
+        let (w, dev) = small_unimodular_matrices()
+            .map(|w| {
+                let wf = imatrix_to_matrix3f(w);
+                (w, (wf.transpose() * ga * wf - gb).amax())
+            })
+            .min_by(|x, y| x.1.total_cmp(&y.1))?;
+        if dev > 2.0 * tol * scale {
+            return None;
+        }
+        // choose a proper rotation using the inversion symmetry
+        let w = if (a * imatrix_to_matrix3f(w)).determinant() * b.determinant() < 0.0 {
+            w.map(|r| r.map(|x| -x))
+        } else {
+            w
+        };
+        let r = b * get_inv_matrix(&(a * imatrix_to_matrix3f(w)));
+        let (r, _) = polar_decomposition(r);
+
+        // B = R A Maᵀ W Mb^-T
+        let m = imatrix_mul(
+            imatrix_mul(imatrix_inv_unimodular(mb), imatrix_transpose(w)),
+            ma,
+        );
+        Some((m, Rotation3::from_matrix_unchecked(r)))
+    }
+}
+// e83d2f61 ends here
+
+// [[file:../lattice.note::0b6d71fa][0b6d71fa]]
+#[test]
+fn test_point_group_operations() {
+    let lat = Lattice::from_params(4.0, 4.0, 4.0, 90.0, 90.0, 90.0);
+    assert_eq!(lat.point_group_operations(1e-3).len(), 48);
+    let lat = Lattice::new([[0.0, 2.0, 2.0], [2.0, 0.0, 2.0], [2.0, 2.0, 0.0]]);
+    assert_eq!(lat.point_group_operations(1e-3).len(), 48);
+    let lat = Lattice::from_params(3.0, 3.0, 5.0, 90.0, 90.0, 120.0);
+    assert_eq!(lat.point_group_operations(1e-3).len(), 24);
+    let lat = Lattice::from_params(3.0, 3.0, 5.0, 90.0, 90.0, 90.0);
+    assert_eq!(lat.point_group_operations(1e-3).len(), 16);
+    let lat = Lattice::from_params(3.0, 4.0, 5.0, 90.0, 90.0, 90.0);
+    assert_eq!(lat.point_group_operations(1e-3).len(), 8);
+    let lat = Lattice::from_params(3.0, 4.0, 5.0, 90.0, 100.0, 90.0);
+    assert_eq!(lat.point_group_operations(1e-3).len(), 4);
+    let lat = Lattice::from_params(3.0, 4.0, 5.0, 80.0, 100.0, 70.0);
+    assert_eq!(lat.point_group_operations(1e-3).len(), 2);
+
+    // operations keep the metric tensor in the original basis
+    let lat = lat.supercell([[1, 2, 0], [0, 1, 0], [1, 0, 1]]);
+    let g = lat.matrix().transpose() * lat.matrix();
+    for w in lat.point_group_operations(1e-3) {
+        let w = imatrix_to_matrix3f(w);
+        approx::assert_relative_eq!(w.transpose() * g * w, g, epsilon = 1e-6);
+    }
+}
+
+#[test]
+fn test_lattice_equivalence() {
+    use vecfx::approx::*;
+
+    let lat = Lattice::from_params(3.0, 4.0, 5.0, 80.0, 100.0, 70.0);
+    // another basis in another orientation
+    let mut other = lat.supercell([[1, 2, 0], [0, 1, 0], [1, 1, 1]]);
+    other.rotate_about([1.0, -1.0, 0.5], 37.0);
+
+    let (m, r) = lat.is_equivalent(&other, 1e-6).unwrap();
+    assert_eq!(imatrix_det(m).abs(), 1);
+    let mut lat1 = lat.supercell(m);
+    lat1.rotate(r);
+    assert_relative_eq!(lat1.matrix(), other.matrix(), epsilon = 1e-8);
+
+    // slightly relaxed
+    let mut relaxed = other;
+    relaxed.scale_by_a(1.001);
+    assert!(lat.is_equivalent(&relaxed, 1e-6).is_none());
+    assert!(lat.is_equivalent(&relaxed, 1e-2).is_some());
+
+    // fcc in primitive and conventional settings are different cells
+    let fcc = Lattice::new([[0.0, 2.0, 2.0], [2.0, 0.0, 2.0], [2.0, 2.0, 0.0]]);
+    let cubic = Lattice::from_params(4.0, 4.0, 4.0, 90.0, 90.0, 90.0);
+    assert!(fcc.is_equivalent(&cubic, 1e-6).is_none());
+    let fcc1 = Lattice::new([[2.0, 2.0, 0.0], [0.0, -2.0, 2.0], [-2.0, 0.0, 2.0]]);
+    assert!(fcc.is_equivalent(&fcc1, 1e-6).is_some());
+}
+// 0b6d71fa ends here
@@ -25,6 +25,13 @@ pub(crate) fn get_cell_lengths(mat: Matrix3f) -> [f64; 3] {
     ]
 }
 
+// return the perpendicular widths of the cell
+pub(crate) fn get_cell_widths(mat: Matrix3f) -> [f64; 3] {
+    let volume = get_cell_volume(mat).abs();
+    let [a, b, c] = get_cell_lengths(mat);
+    [volume / (b * c), volume / (c * a), volume / (a * b)]
+}
+
 // return cell angle parameters in degrees
 pub(crate) fn get_cell_angles(mat: Matrix3f) -> [f64; 3] {
     let va = mat.column(0);
@@ -46,3 +53,114 @@ pub(crate) fn get_cell_angles(mat: Matrix3f) -> [f64; 3] {
 //     }
 // }
 // core:1 ends here
+
+// [[file:../lattice.note::3b9c52e1][3b9c52e1]]
+// integer transformation matrix: row `i` holds the coefficients of new
+// lattice vector `i` in terms of old lattice vectors
+
+// lattice matrix of the supercell defined by integer matrix `m`
+pub(crate) fn get_supercell_matrix(matrix: &Matrix3f, m: [[isize; 3]; 3]) -> Matrix3f {
+    matrix * imatrix_to_matrix3f(m).transpose()
+}
+
+// convert integer matrix into float matrix, keeping its layout
+pub(crate) fn imatrix_to_matrix3f(m: [[isize; 3]; 3]) -> Matrix3f {
+    Matrix3f::from_fn(|i, j| m[i][j] as f64)
+}
+
+// determinant of integer matrix
+pub(crate) fn imatrix_det(m: [[isize; 3]; 3]) -> isize {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+// 3b9c52e1 ends here
+
+// [[file:../lattice.note::5c0f7e2d][5c0f7e2d]]
+// convert float matrix into integer matrix by rounding
+pub(crate) fn matrix3f_to_imatrix(m: Matrix3f) -> [[isize; 3]; 3] {
+    std::array::from_fn(|i| std::array::from_fn(|j| m[(i, j)].round() as isize))
+}
+
+// product of two integer matrices
+pub(crate) fn imatrix_mul(a: [[isize; 3]; 3], b: [[isize; 3]; 3]) -> [[isize; 3]; 3] {
+    std::array::from_fn(|i| std::array::from_fn(|j| (0..3).map(|k| a[i][k] * b[k][j]).sum()))
+}
+
+// product of integer matrix and integer vector
+pub(crate) fn imatrix_mul_vec(m: [[isize; 3]; 3], v: [isize; 3]) -> [isize; 3] {
+    m.map(|row| (0..3).map(|k| row[k] * v[k]).sum())
+}
+
+// transpose of integer matrix
+pub(crate) fn imatrix_transpose(m: [[isize; 3]; 3]) -> [[isize; 3]; 3] {
+    std::array::from_fn(|i| std::array::from_fn(|j| m[j][i]))
+}
+
+// inverse of unimodular integer matrix
+pub(crate) fn imatrix_inv_unimodular(m: [[isize; 3]; 3]) -> [[isize; 3]; 3] {
+    assert_eq!(imatrix_det(m).abs(), 1, "not a unimodular matrix: {m:?}");
+    matrix3f_to_imatrix(get_inv_matrix(&imatrix_to_matrix3f(m)))
+}
+// 5c0f7e2d ends here
+
+// [[file:../lattice.note::8e41a2c6][8e41a2c6]]
+// greatest common divisor, always non-negative
+pub(crate) fn gcd(a: isize, b: isize) -> isize {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// extended Euclid algorithm: returns (x, y) such that a x + b y = gcd(a, b)
+pub(crate) fn ext_gcd(a: isize, b: isize) -> (isize, isize) {
+    if b == 0 {
+        (a.signum(), 0)
+    } else {
+        let (x, y) = ext_gcd(b, a % b);
+        (y, x - (a / b) * y)
+    }
+}
+// 8e41a2c6 ends here
+
+// [[file:../lattice.note::4f9b06e2][4f9b06e2]]
+// complementary error function with accuracy close to machine precision
+pub(crate) fn erfc(x: f64) -> f64 {
+    use std::f64::consts::PI;
+
+    if x < 0.0 {
+        2.0 - erfc(-x)
+    } else if x < 2.5 {
+        // erf(x) = 2/√π exp(-x^2) Σ 2^n x^(2n+1) / (2n+1)!!
+        let x2 = x * x;
+        let mut term = x;
+        let mut sum = x;
+        for n in 1..200 {
+            term *= 2.0 * x2 / (2 * n + 1) as f64;
+            sum += term;
+            if term < sum * 1e-17 {
+                break;
+            }
+        }
+        1.0 - 2.0 / PI.sqrt() * (-x2).exp() * sum
+    } else {
+        // continued fraction: erfc(x) = exp(-x^2)/√π 1/(x + 1/2/(x + 1/(x + 3/2/(x + ...))))
+        let mut f = x;
+        for n in (1..100).rev() {
+            f = x + 0.5 * n as f64 / f;
+        }
+        (-x * x).exp() / PI.sqrt() / f
+    }
+}
+// 4f9b06e2 ends here
+
+// [[file:../lattice.note::a6d3f85b][a6d3f85b]]
+// apply scalar function `f` to the eigenvalues of symmetric matrix `m`
+pub(crate) fn sym_matrix_map(m: Matrix3f, f: impl Fn(f64) -> f64) -> Matrix3f {
+    let eigen = m.symmetric_eigen();
+    let d = Matrix3f::from_diagonal(&eigen.eigenvalues.map(f));
+    eigen.eigenvectors * d * eigen.eigenvectors.transpose()
+}
+// a6d3f85b ends here
@@ -0,0 +1,245 @@
+// [[file:../lattice.note::e5a1d7c3][e5a1d7c3]]
+use crate::Lattice;
+
+use gchemol_gut::itertools::*;
+use vecfx::*;
+// e5a1d7c3 ends here
+
+// [[file:../lattice.note::93b0f6e4][93b0f6e4]]
+/// A face of a [`Polyhedron`].
+#[derive(Debug, Clone)]
+pub struct Face {
+    vertices: Vec<usize>,
+    normal: Vector3f,
+    distance: f64,
+    area: f64,
+}
+
+impl Face {
+    /// Indices of the face vertices, ordered counterclockwise when viewed
+    /// from outside.
+    pub fn vertices(&self) -> &[usize] {
+        &self.vertices
+    }
+
+    /// The unit outward normal vector.
+    pub fn normal(&self) -> Vector3f {
+        self.normal
+    }
+
+    /// The distance from the center to the face plane.
+    pub fn distance(&self) -> f64 {
+        self.distance
+    }
+
+    /// The area of the face.
+    pub fn area(&self) -> f64 {
+        self.area
+    }
+}
+
+/// A convex polyhedron centered at the origin, such as the Wigner-Seitz
+/// cell or the first Brillouin zone.
+#[derive(Debug, Clone)]
+pub struct Polyhedron {
+    vertices: Vec<Vector3f>,
+    faces: Vec<Face>,
+}
+
+impl Polyhedron {
+    /// Cartesian coordinates of the vertices.
+    pub fn vertices(&self) -> &[Vector3f] {
+        &self.vertices
+    }
+
+    /// The faces of the polyhedron.
+    pub fn faces(&self) -> &[Face] {
+        &self.faces
+    }
+
+    /// Return the edges as pairs of vertex indices.
+    pub fn edges(&self) -> Vec<[usize; 2]> {
+        self.faces
+            .iter()
+            .flat_map(|f| {
+                let n = f.vertices.len();
+                (0..n).map(move |i| {
+                    let (a, b) = (f.vertices[i], f.vertices[(i + 1) % n]);
+                    [a.min(b), a.max(b)]
+                })
+            })
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
+    /// The total area of all faces.
+    pub fn area(&self) -> f64 {
+        self.faces.iter().map(|f| f.area).sum()
+    }
+
+    /// The volume of the polyhedron.
+    pub fn volume(&self) -> f64 {
+        self.faces.iter().map(|f| f.area * f.distance / 3.0).sum()
+    }
+
+    /// Return true if point `p` in Cartesian coordinates is inside the
+    /// polyhedron or on its surface within tolerance `tol`.
+    pub fn contains<T: Into<Vector3f>>(&self, p: T, tol: f64) -> bool {
+        let p = p.into();
+        self.faces
+            .iter()
+            .all(|f| f.normal.dot(&p) <= f.distance + tol)
+    }
+}
+
+// Construct the Voronoi cell of the lattice point at the origin, bounded by
+// the bisecting planes of the candidate lattice vectors.
+fn voronoi_cell(candidates: &[Vector3f]) -> Polyhedron {
+    let scale = candidates.iter().map(|t| t.norm()).fold(0.0, f64::max);
+    let tol = 1e-8 * scale;
+    let inside = |x: &Vector3f| {
+        candidates
+            .iter()
+            .all(|t| x.dot(t) <= 0.5 * t.norm_squared() + tol * t.norm())
+    };
+
+    // vertices: intersections of three planes inside all half spaces
+    let mut vertices: Vec<Vector3f> = vec![];
+    for (t1, t2, t3) in candidates.iter().tuple_combinations() {
+        let m = Matrix3f::from_rows(&[t1.transpose(), t2.transpose(), t3.transpose()]);
+        let b = Vector3f::from([t1.norm_squared(), t2.norm_squared(), t3.norm_squared()]) * 0.5;
+        if m.determinant().abs() < 1e-8 * scale.powi(3) {
+            continue;
+        }
+        let x = m.try_inverse().unwrap() * b;
+        if inside(&x) && vertices.iter().all(|v| (v - x).norm() > 1e-6 * scale) {
+            vertices.push(x);
+        }
+    }
+
+    // faces: planes containing at least three vertices
+    let mut faces = vec![];
+    for t in candidates {
+        let normal = t.normalize();
+        let distance = 0.5 * t.norm();
+        let on_plane: Vec<_> = (0..vertices.len())
+            .filter(|&i| (vertices[i].dot(&normal) - distance).abs() < 1e-6 * scale)
+            .collect();
+        if on_plane.len() < 3 {
+            continue;
+        }
+        // sort counterclockwise around the face center
+        let center: Vector3f =
+            on_plane.iter().map(|&i| vertices[i]).sum::<Vector3f>() / on_plane.len() as f64;
+        let u = (vertices[on_plane[0]] - center).normalize();
+        let w = normal.cross(&u);
+        let angle = |i: usize| {
+            let d = vertices[i] - center;
+            d.dot(&w).atan2(d.dot(&u))
+        };
+        let ordered: Vec<_> = on_plane
+            .into_iter()
+            .sorted_by(|&i, &j| angle(i).total_cmp(&angle(j)))
+            .collect();
+        let n = ordered.len();
+        let area = (0..n)
+            .map(|k| {
+                (vertices[ordered[k]] - center)
+                    .cross(&(vertices[ordered[(k + 1) % n]] - center))
+                    .dot(&normal)
+            })
+            .sum::<f64>()
+            * 0.5;
+        faces.push(Face {
+            vertices: ordered,
+            normal,
+            distance,
+            area,
+        });
+    }
+
+    Polyhedron { vertices, faces }
+}
+
+impl Lattice {
+    /// Return the Wigner-Seitz cell centered at the lattice point at the
+    /// Cartesian origin (the lattice origin is not included).
+    ///
+    /// The cell is bounded by the bisecting planes of the Voronoi-relevant
+    /// lattice vectors, which are searched among the combinations of the
+    /// Niggli reduced basis vectors with coefficients in {-1, 0, 1}.
+    pub fn wigner_seitz_cell(&self) -> Polyhedron {
+        let (reduced, _) = self.niggli_reduce();
+        let candidates: Vec<_> = reduced
+            .replicate(-1..=1, -1..=1, -1..=1)
+            .filter(|f| f.norm_squared() > 0.0)
+            .map(|f| reduced.matrix() * f)
+            .collect();
+        voronoi_cell(&candidates)
+    }
+
+    /// Return the first Brillouin zone, i.e. the Wigner-Seitz cell of the
+    /// reciprocal lattice with the factor of 2π.
+    pub fn brillouin_zone(&self) -> Polyhedron {
+        self.reciprocal_2pi().wigner_seitz_cell()
+    }
+}
+// 93b0f6e4 ends here
+
+// [[file:../lattice.note::2c49e8d0][2c49e8d0]]
+#[test]
+fn test_wigner_seitz_cell() {
+    use vecfx::approx::*;
+
+    let check = |ws: &Polyhedron, nv: usize, ne: usize, nf: usize, volume: f64| {
+        assert_eq!(ws.vertices().len(), nv);
+        assert_eq!(ws.edges().len(), ne);
+        assert_eq!(ws.faces().len(), nf);
+        assert_relative_eq!(ws.volume(), volume, max_relative = 1e-8);
+    };
+
+    // simple cubic: cube
+    let lat = Lattice::from_params(2.0, 2.0, 2.0, 90.0, 90.0, 90.0);
+    let ws = lat.wigner_seitz_cell();
+    check(&ws, 8, 12, 6, 8.0);
+    assert_relative_eq!(ws.area(), 24.0, epsilon = 1e-8);
+    assert!(ws.contains([0.99, -0.99, 0.5], 1e-8));
+    assert!(!ws.contains([1.01, 0.0, 0.0], 1e-8));
+
+    // fcc: rhombic dodecahedron, and its Brillouin zone is a truncated octahedron
+    let fcc = Lattice::new([[0.0, 2.0, 2.0], [2.0, 0.0, 2.0], [2.0, 2.0, 0.0]]);
+    check(&fcc.wigner_seitz_cell(), 14, 24, 12, 16.0);
+    let bz = fcc.brillouin_zone();
+    check(&bz, 24, 36, 14, fcc.reciprocal_2pi().volume());
+    // X point at the center of square face
+    let x = fcc.reciprocal_2pi().to_cart([0.5, 0.0, 0.5]);
+    assert!(bz.contains(x, 1e-8));
+    assert!(!bz.contains(x * 1.01, 1e-8));
+
+    // bcc: truncated octahedron
+    let bcc = Lattice::new([[-1.5, 1.5, 1.5], [1.5, -1.5, 1.5], [1.5, 1.5, -1.5]]);
+    check(&bcc.wigner_seitz_cell(), 24, 36, 14, 13.5);
+    check(
+        &bcc.brillouin_zone(),
+        14,
+        24,
+        12,
+        bcc.reciprocal_2pi().volume(),
+    );
+
+    // hexagonal prism
+    let hex = Lattice::from_params(3.0, 3.0, 5.0, 90.0, 90.0, 120.0);
+    check(&hex.wigner_seitz_cell(), 12, 18, 8, hex.volume());
+
+    // triclinic
+    let lat = Lattice::from_params(3.0, 4.0, 5.0, 70.0, 100.0, 110.0);
+    let ws = lat.wigner_seitz_cell();
+    assert_relative_eq!(ws.volume(), lat.volume(), max_relative = 1e-8);
+    // Euler's formula
+    assert_eq!(ws.vertices().len() + ws.faces().len(), ws.edges().len() + 2);
+    for f in ws.faces() {
+        assert!(f.area() > 0.0);
+    }
+}
+// 2c49e8d0 ends here
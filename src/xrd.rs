@@ -0,0 +1,186 @@
+// [[file:../lattice.note::a93f0c27][a93f0c27]]
+use crate::utils::*;
+use crate::Lattice;
+
+use gchemol_gut::itertools::*;
+use std::collections::HashMap;
+// a93f0c27 ends here
+
+// [[file:../lattice.note::3e7d6b14][3e7d6b14]]
+/// X-ray wavelength for diffraction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Wavelength {
+    /// Cu Kα1: 1.5406 Angstrom
+    CuKa,
+    /// Mo Kα1: 0.70930 Angstrom
+    MoKa,
+    /// Custom wavelength in Angstrom
+    Custom(f64),
+}
+
+impl Wavelength {
+    /// The wavelength in Angstrom.
+    pub fn value(&self) -> f64 {
+        match self {
+            Self::CuKa => 1.5406,
+            Self::MoKa => 0.70930,
+            Self::Custom(x) => *x,
+        }
+    }
+}
+
+impl From<f64> for Wavelength {
+    fn from(x: f64) -> Self {
+        Self::Custom(x)
+    }
+}
+
+/// Lattice centering types for reflection conditions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Centering {
+    /// Primitive: no condition
+    P,
+    /// Body centered: h + k + l = 2n
+    I,
+    /// Face centered: h, k, l all odd or all even
+    F,
+    /// A-face centered: k + l = 2n
+    A,
+    /// B-face centered: h + l = 2n
+    B,
+    /// C-face centered: h + k = 2n
+    C,
+    /// Rhombohedral in obverse hexagonal setting: -h + k + l = 3n
+    R,
+}
+
+impl Centering {
+    /// Return true if reflection `hkl` is not extinct for this centering.
+    pub fn is_allowed(&self, hkl: [isize; 3]) -> bool {
+        let [h, k, l] = hkl;
+        match self {
+            Self::P => true,
+            Self::I => (h + k + l) % 2 == 0,
+            Self::F => (h + k) % 2 == 0 && (k + l) % 2 == 0,
+            Self::A => (k + l) % 2 == 0,
+            Self::B => (h + l) % 2 == 0,
+            Self::C => (h + k) % 2 == 0,
+            Self::R => (-h + k + l) % 3 == 0,
+        }
+    }
+}
+
+/// A powder diffraction peak, merging all symmetry-equivalent reflections.
+#[derive(Debug, Clone, Copy)]
+pub struct Reflection {
+    /// Miller indices of the representative reflection
+    pub hkl: [isize; 3],
+    /// interplanar spacing in Angstrom
+    pub d: f64,
+    /// Bragg angle 2θ in degrees
+    pub two_theta: f64,
+    /// number of symmetry-equivalent reflections
+    pub multiplicity: usize,
+}
+
+impl Lattice {
+    /// Return powder diffraction peak positions up to `two_theta_max` (in
+    /// degrees), sorted by increasing 2θ.
+    ///
+    /// Symmetry-equivalent reflections are grouped under the point-group
+    /// operations of the lattice (see [`Lattice::point_group_operations`]),
+    /// which include Friedel pairs. Since the atomic basis is not known, the
+    /// multiplicities are those of the lattice holohedry. Reflections
+    /// extinct for `centering` are removed.
+    pub fn powder_reflections(
+        &self,
+        wavelength: impl Into<Wavelength>,
+        two_theta_max: f64,
+        centering: Option<Centering>,
+    ) -> Vec<Reflection> {
+        let lambda = wavelength.into().value();
+        assert!(lambda > 0.0, "invalid wavelength: {lambda}");
+        let theta_max = (0.5 * two_theta_max.min(180.0)).to_radians();
+        // Bragg's law: λ = 2 d sinθ
+        let g_max = 2.0 * theta_max.sin() / lambda;
+        let [na, nb, nc] = self.lengths().map(|x| (g_max * x).floor() as isize);
+
+        // Miller indices transform as h' = W^T h
+        let ops: Vec<_> = self
+            .point_group_operations(1e-3)
+            .into_iter()
+            .map(imatrix_transpose)
+            .collect();
+        let centering = centering.unwrap_or(Centering::P);
+        let mut peaks: HashMap<[isize; 3], Vec<[isize; 3]>> = HashMap::new();
+        for (h, k, l) in iproduct!(-na..=na, -nb..=nb, -nc..=nc) {
+            let hkl = [h, k, l];
+            if hkl == [0, 0, 0] || !centering.is_allowed(hkl) {
+                continue;
+            }
+            if 1.0 / self.d_spacing(h, k, l) > g_max {
+                continue;
+            }
+            let key = ops.iter().map(|&w| imatrix_mul_vec(w, hkl)).max().unwrap();
+            peaks.entry(key).or_default().push(hkl);
+        }
+
+        let mut reflections: Vec<_> = peaks
+            .into_iter()
+            .map(|(hkl, members)| {
+                let [h, k, l] = hkl;
+                let d = self.d_spacing(h, k, l);
+                let two_theta = 2.0 * (lambda / (2.0 * d)).asin().to_degrees();
+                Reflection {
+                    hkl,
+                    d,
+                    two_theta,
+                    multiplicity: members.len(),
+                }
+            })
+            .collect();
+        reflections.sort_by(|a, b| a.two_theta.total_cmp(&b.two_theta).then(b.hkl.cmp(&a.hkl)));
+        reflections
+    }
+}
+// 3e7d6b14 ends here
+
+// [[file:../lattice.note::60cb8d1f][60cb8d1f]]
+#[test]
+fn test_powder_reflections() {
+    use vecfx::approx::*;
+
+    // silicon, conventional cubic cell
+    let lat = Lattice::from_params(5.431, 5.431, 5.431, 90.0, 90.0, 90.0);
+    let peaks = lat.powder_reflections(Wavelength::CuKa, 60.0, Some(Centering::F));
+    let hkls: Vec<_> = peaks.iter().map(|p| p.hkl).collect();
+    assert_eq!(
+        hkls,
+        [[1, 1, 1], [2, 0, 0], [2, 2, 0], [3, 1, 1], [2, 2, 2]]
+    );
+    let mults: Vec<_> = peaks.iter().map(|p| p.multiplicity).collect();
+    assert_eq!(mults, [8, 6, 12, 24, 8]);
+    assert_relative_eq!(peaks[0].two_theta, 28.44, epsilon = 1e-2);
+    assert_relative_eq!(peaks[0].d, 5.431 / 3f64.sqrt(), epsilon = 1e-8);
+
+    // the same peaks from the primitive cell
+    let prim = lat.supercell([[0, 1, 1], [1, 0, 1], [1, 1, 0]]);
+    let prim = Lattice::from_matrix(prim.matrix() * 0.5);
+    let peaks_prim = prim.powder_reflections(1.5406, 60.0, None);
+    assert_eq!(peaks_prim.len(), peaks.len());
+    for (p1, p2) in peaks.iter().zip(peaks_prim.iter()) {
+        assert_relative_eq!(p1.two_theta, p2.two_theta, epsilon = 1e-8);
+        assert_eq!(p1.multiplicity, p2.multiplicity);
+    }
+
+    // hexagonal cell
+    let lat = Lattice::from_params(3.0, 3.0, 5.0, 90.0, 90.0, 120.0);
+    let peaks = lat.powder_reflections(Wavelength::MoKa, 20.0, None);
+    let p = peaks.iter().find(|p| p.hkl == [0, 0, 1]).unwrap();
+    assert_eq!(p.multiplicity, 2);
+    let p = peaks.iter().find(|p| p.hkl == [1, 0, 0]).unwrap();
+    assert_eq!(p.multiplicity, 6);
+    let p = peaks.iter().find(|p| p.hkl == [1, 0, 1]).unwrap();
+    assert_eq!(p.multiplicity, 12);
+}
+// 60cb8d1f ends here
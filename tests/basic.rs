@@ -5,6 +5,11 @@ use approx::*;
 use vecfx::*;
 
 #[test]
+#[allow(
+    unused_variables,
+    clippy::bool_assert_comparison,
+    clippy::clone_on_copy
+)]
 fn test_lattice_construct() {
     let mut lat = Lattice::default();
     let loc = [1.0, 2.0, 3.0];
@@ -72,6 +77,90 @@ fn test_lattice_volume() {
     assert_relative_eq!(8000.0, lat.volume(), epsilon = 1e-4);
 }
 
+#[test]
+fn test_lattice_set_volume() {
+    let lat = Lattice::from_params(3.0, 4.0, 5.0, 80.0, 100.0, 70.0);
+
+    let mut l1 = lat;
+    l1.set_volume(100.0);
+    assert_relative_eq!(l1.volume(), 100.0, epsilon = 1e-8);
+    assert_relative_eq!(
+        l1.angles().as_slice(),
+        lat.angles().as_slice(),
+        epsilon = 1e-8
+    );
+
+    // keep c fixed for slabs
+    let mut l1 = lat;
+    l1.set_volume_fixed_axis(100.0, 2);
+    assert_relative_eq!(l1.volume(), 100.0, epsilon = 1e-8);
+    assert_relative_eq!(l1.vector_c(), lat.vector_c(), epsilon = 1e-8);
+
+    // diamond: 8 carbon atoms in conventional cell of 3.567 A
+    let mass = 8.0 * 12.011;
+    let mut l1 = Lattice::from_params(3.0, 3.0, 3.0, 90.0, 90.0, 90.0);
+    l1.scale_to_density(mass, 3.51);
+    assert_relative_eq!(l1.lengths()[0], 3.567, epsilon = 3e-3);
+    assert_relative_eq!(l1.density(mass), 3.51, epsilon = 1e-8);
+    let mut l1 = lat;
+    l1.scale_to_density_fixed_axis(mass, 3.51, 0);
+    assert_relative_eq!(l1.density(mass), 3.51, epsilon = 1e-8);
+    assert_relative_eq!(l1.lengths()[0], 3.0, epsilon = 1e-8);
+}
+
+#[test]
+fn test_lattice_approx_eq() {
+    // cell parameters with rounding noise
+    let lat = Lattice::new([
+        [4.0000001, 0.0, 0.0],
+        [0.0000002, 3.9999999, 0.0],
+        [0.0, -0.0000001, 4.0000002],
+    ]);
+    assert!(!lat.is_orthorhombic());
+    assert!(lat.is_orthorhombic_tol(1e-6));
+    assert!(lat.is_tetragonal(1e-6));
+    assert!(lat.is_cubic(1e-6));
+    assert!(!lat.is_cubic(1e-9));
+    assert!(!lat.is_hexagonal(1e-6));
+
+    let ref_lat = Lattice::from_params(4.0, 4.0, 4.0, 90.0, 90.0, 90.0);
+    assert_ne!(lat, ref_lat);
+    assert_relative_eq!(lat, ref_lat, epsilon = 1e-6);
+    assert_abs_diff_ne!(lat, ref_lat, epsilon = 1e-9);
+    let mut lat1 = ref_lat;
+    lat1.set_origin([0.0, 0.0, 1.0]);
+    assert_relative_ne!(lat1, ref_lat, epsilon = 1e-6);
+
+    let lat = Lattice::from_params(3.0, 3.0, 5.0, 90.0, 90.0, 120.0);
+    assert!(lat.is_hexagonal(1e-8));
+    assert!(!lat.is_orthorhombic_tol(1e-8));
+    let lat = Lattice::from_params(3.0, 3.0, 5.0, 90.0, 90.0, 90.0);
+    assert!(lat.is_tetragonal(1e-8));
+    assert!(!lat.is_cubic(1e-8));
+}
+
+#[test]
+fn test_lattice_map_positions() {
+    let mut old = Lattice::from_params(3.0, 4.0, 5.0, 90.0, 90.0, 90.0);
+    old.set_origin([1.0, 1.0, 1.0]);
+    let mut new = Lattice::from_params(3.3, 4.0, 5.5, 80.0, 100.0, 70.0);
+    new.set_origin([-1.0, 0.0, 2.0]);
+
+    let positions = [[1.0, 1.0, 1.0], [2.5, 3.0, 0.5], [4.0, 5.0, 6.0]];
+    let mapped = old.map_positions_to(&new, &positions);
+    for (p, q) in positions.iter().zip(&mapped) {
+        assert_relative_eq!(old.to_frac(*p), new.to_frac(*q), epsilon = 1e-10);
+    }
+    // the origin is mapped onto the new origin
+    assert_relative_eq!(Vector3f::from(mapped[0]), new.origin(), epsilon = 1e-10);
+
+    let mut positions = positions;
+    old.map_positions_to_mut(&new, &mut positions);
+    for (p, q) in positions.iter().zip(&mapped) {
+        assert_relative_eq!(p.as_slice(), q.as_slice(), epsilon = 1e-10);
+    }
+}
+
 #[test]
 fn test_lattice_frac_cart() {
     // ovito/tests/files/LAMMPS/multi_sequence_1.dump
@@ -93,6 +182,7 @@ fn test_lattice_frac_cart() {
 }
 
 #[test]
+#[allow(clippy::useless_conversion)]
 // adopted from lumol
 fn test_wrap() {
     // Cubic unit cell
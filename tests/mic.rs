@@ -10,6 +10,7 @@ use approx::*;
 use vecfx::*;
 
 #[test]
+#[allow(clippy::approx_constant)]
 fn test_mic_distance() {
     // Setup lattice
     // a = b = c = 4, alpha = beta = gamma = 60